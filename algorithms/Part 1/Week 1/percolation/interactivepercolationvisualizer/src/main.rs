@@ -9,6 +9,11 @@ use std::fmt::Write;
 const SAVE_SCREEN: usize = 1;
 const SAVE_TEXT: usize = 2;
 const NEW: usize = 3;
+const UNDO: usize = 4;
+const REDO: usize = 5;
+const SAVE_SESSION: usize = 6;
+const RESUME_SESSION: usize = 7;
+const OPEN_INPUT: usize = 8;
 
 struct KeyCharCallback;
 
@@ -49,6 +54,21 @@ fn main() {
     menu.add_item("Save as text", SAVE_TEXT)
         .shortcut(Key::T, MENU_KEY_CTRL)
         .build();
+    menu.add_item("Save session", SAVE_SESSION)
+        .shortcut(Key::E, MENU_KEY_CTRL)
+        .build();
+    menu.add_item("Resume session", RESUME_SESSION)
+        .shortcut(Key::O, MENU_KEY_CTRL)
+        .build();
+    menu.add_item("Open input file", OPEN_INPUT)
+        .shortcut(Key::I, MENU_KEY_CTRL)
+        .build();
+    menu.add_item("Undo", UNDO)
+        .shortcut(Key::Z, MENU_KEY_CTRL)
+        .build();
+    menu.add_item("Redo", REDO)
+        .shortcut(Key::Y, MENU_KEY_CTRL)
+        .build();
 
     if let Some(menus) = window.get_posix_menus() {
         println!("Menus {:?}", menus);
@@ -58,7 +78,7 @@ fn main() {
     window.set_icon(<Icon as std::str::FromStr>::from_str("percolation.ico").unwrap());
     let mut perc = Percolation::new(n);
     let mut block_half_length = half_length(n);
-    let mut leftclick = false;
+    let mut mousedown = false;
     let font = set_font();
 
     while window.is_open() && !window.is_key_down(Key::Escape) {
@@ -67,39 +87,24 @@ fn main() {
                 ((idx / LENGTH as f32 * n as f32) + 1.) as usize,
                 ((idy / LENGTH as f32 * n as f32) + 1.) as usize,
             );
-            if leftclick {
-                leftclick = false;
-            } else {
-                leftclick = window.get_mouse_down(MouseButton::Left);
-            }
-            if leftclick {
-                if idy <= n {
-                    perc.open(idy, idx);
+            // only act on the press itself so that holding the button does not keep toggling
+            let leftclick = window.get_mouse_down(MouseButton::Left) && !mousedown;
+            mousedown = window.get_mouse_down(MouseButton::Left);
+            if leftclick && idy <= n {
+                if perc.is_open(idy, idx) {
+                    perc.close(idy, idx);
+                    text_buf = text_buf.replacen(&format!("\n{} {}\n", idy, idx), "\n", 1);
+                    buffer = vec![0; AREA];
+                    fill_grid(&perc, n, block_half_length, &mut buffer);
+                } else {
+                    let outcome = perc.open(idy, idx);
                     writeln!(&mut text_buf, "{} {}", idy, idx).unwrap();
-                    for idx in 1..=n {
-                        for idy in 1..=n {
-                            if perc.is_open(idy, idx) {
-                                if perc.is_full(idy, idx) {
-                                    fill_rect(idx, idy, n, block_half_length, 6801139, &mut buffer);
-                                //rgb(103,198,243)
-                                } else {
-                                    fill_rect(
-                                        idx,
-                                        idy,
-                                        n,
-                                        block_half_length,
-                                        16777215,
-                                        &mut buffer,
-                                    );
-                                }
-                            }
-                        }
-                    }
+                    fill_outcome(idx, idy, &outcome, n, block_half_length, &mut buffer);
                 }
             }
         }
         let mut offscreenbuffer = buffer.clone();
-        offscreenbuffer.extend(draw_status_bar(&perc, true, &font));
+        offscreenbuffer.extend(draw_status_bar(&perc, true, None, &font));
         if let Some(menu_id) = window.is_menu_pressed() {
             match menu_id {
                 SAVE_SCREEN => {
@@ -108,20 +113,78 @@ fn main() {
                 SAVE_TEXT => {
                     save_text(&text_buf);
                 }
+                SAVE_SESSION => {
+                    save_snapshot(&perc);
+                }
+                RESUME_SESSION | OPEN_INPUT => {
+                    let opened = if menu_id == OPEN_INPUT {
+                        open().and_then(|input| match Percolation::try_new(input.n) {
+                            Ok(mut p) => {
+                                p.open_many(input.sites);
+                                Some(p)
+                            }
+                            Err(e) => {
+                                message_box("Invalid input file", e.to_string().as_str());
+                                None
+                            }
+                        })
+                    } else {
+                        open_snapshot()
+                    };
+                    if let Some(p) = opened {
+                        n = p.grid_size();
+                        perc = p;
+                        block_half_length = half_length(n);
+                        buffer = vec![0; AREA];
+                        fill_grid(&perc, n, block_half_length, &mut buffer);
+                        text_buf = String::with_capacity(AREA * 8);
+                        writeln!(&mut text_buf, "{}", n).unwrap();
+                        for (idy, idx) in perc.open_sites() {
+                            writeln!(&mut text_buf, "{} {}", idy, idx).unwrap();
+                        }
+                    }
+                }
+                UNDO => {
+                    // undoing a close opens the site again
+                    if let Some((idy, idx)) = perc.undo() {
+                        if perc.is_open(idy, idx) {
+                            writeln!(&mut text_buf, "{} {}", idy, idx).unwrap();
+                        } else {
+                            text_buf = text_buf.replacen(&format!("\n{} {}\n", idy, idx), "\n", 1);
+                        }
+                        buffer = vec![0; AREA];
+                        fill_grid(&perc, n, block_half_length, &mut buffer);
+                    }
+                }
+                REDO => {
+                    if let Some(((idy, idx), outcome)) = perc.redo() {
+                        if outcome.opened {
+                            writeln!(&mut text_buf, "{} {}", idy, idx).unwrap();
+                            fill_outcome(idx, idy, &outcome, n, block_half_length, &mut buffer);
+                        } else {
+                            text_buf = text_buf.replacen(&format!("\n{} {}\n", idy, idx), "\n", 1);
+                            buffer = vec![0; AREA];
+                            fill_grid(&perc, n, block_half_length, &mut buffer);
+                        }
+                    }
+                }
                 NEW => {
                     match input_box(
                         "Start new percolation grid",
                         "Enter new percolation grid size:",
                         n.to_string().as_str(),
                     ) {
-                        Ok(s) => {
-                            n = s;
-                            buffer = vec![0; AREA];
-                            text_buf = String::with_capacity(AREA * 8);
-                            perc = Percolation::new(n);
-                            block_half_length = half_length(n);
-                            writeln!(&mut text_buf, "{}", n).unwrap();
-                        }
+                        Ok(s) => match Percolation::try_new(s) {
+                            Ok(p) => {
+                                n = s;
+                                buffer = vec![0; AREA];
+                                text_buf = String::with_capacity(AREA * 8);
+                                perc = p;
+                                block_half_length = half_length(n);
+                                writeln!(&mut text_buf, "{}", n).unwrap();
+                            }
+                            Err(e) => message_box("Invalid input entered", e.to_string().as_str()),
+                        },
                         Err(e) => message_box("Invalid input entered", e.to_string().as_str()),
                     }
                 }
@@ -0,0 +1,164 @@
+use crate::{Percolation, CLOSE};
+use std::collections::HashMap;
+
+const UNSEEN: usize = usize::MAX;
+
+// Structure of the clusters connecting the sources to the sinks once the system percolates.
+pub struct SpanningCluster {
+    // steps along the shortest path of open sites from a source site to a sink site
+    pub chemical_distance: usize,
+    // sites lying on some self-avoiding path from a source site to a sink site
+    pub backbone: Vec<(usize, usize)>,
+    // sites of the spanning clusters that are not on the backbone
+    pub dangling_ends: Vec<(usize, usize)>,
+}
+
+// The backbone is found as the biconnected component that contains an extra edge between a
+// virtual source joined to every source site and a virtual sink joined to every sink site:
+// a site is on a source to sink path exactly when it is on a cycle through that edge.
+pub fn analyse(perc: &Percolation) -> Option<SpanningCluster> {
+    let chemical_distance = perc.shortest_percolation_path()?.len() - 1;
+    let sites: Vec<usize> = (0..perc.open.len())
+        .filter(|&index| {
+            let root = perc.id.find(index);
+            perc.open[index] != CLOSE
+                && perc.open[root] & perc.source != 0
+                && perc.open[root] & perc.sink != 0
+        })
+        .collect();
+    let node: HashMap<usize, usize> = sites
+        .iter()
+        .enumerate()
+        .map(|(node, &index)| (index, node))
+        .collect();
+    let (source, sink) = (sites.len(), sites.len() + 1);
+    let mut adjacency: Vec<Vec<(usize, usize)>> = vec![Vec::new(); sites.len() + 2];
+    let mut edges = 0;
+    let mut add_edge = |u: usize, v: usize| {
+        adjacency[u].push((v, edges));
+        adjacency[v].push((u, edges));
+        edges += 1;
+    };
+    // added first so that the search below leaves the virtual source through it
+    add_edge(source, sink);
+    for (u, &index) in sites.iter().enumerate() {
+        let status = perc.status(index);
+        if status & perc.source != 0 {
+            add_edge(source, u);
+        }
+        if status & perc.sink != 0 {
+            add_edge(sink, u);
+        }
+        for (idxnear, _) in perc.neighbours(index) {
+            // every grid edge is seen from both ends, keep one of them
+            match node.get(&idxnear) {
+                Some(&v) if idxnear > index => add_edge(u, v),
+                _ => (),
+            }
+        }
+    }
+    let backbone = backbone_nodes(&adjacency, source);
+    Some(SpanningCluster {
+        chemical_distance,
+        backbone: sites
+            .iter()
+            .zip(&backbone)
+            .filter(|&(_, &on)| on)
+            .map(|(&index, _)| perc.coordinates(index))
+            .collect(),
+        dangling_ends: sites
+            .iter()
+            .zip(&backbone)
+            .filter(|&(_, &on)| !on)
+            .map(|(&index, _)| perc.coordinates(index))
+            .collect(),
+    })
+}
+
+// nodes of the biconnected component holding the first edge of `root`, by an iterative
+// Hopcroft-Tarjan search so that large clusters cannot overflow the call stack
+fn backbone_nodes(adjacency: &[Vec<(usize, usize)>], root: usize) -> Vec<bool> {
+    let mut disc = vec![UNSEEN; adjacency.len()];
+    let mut low = vec![UNSEEN; adjacency.len()];
+    let mut time = 0;
+    // (node, edge leading to it, next adjacency entry to look at)
+    let mut frames = vec![(root, UNSEEN, 0)];
+    let mut edge_stack: Vec<(usize, usize)> = Vec::new();
+    let mut component = vec![false; adjacency.len()];
+    disc[root] = time;
+    low[root] = time;
+    while let Some(frame) = frames.last_mut() {
+        let (v, parent_edge, next) = *frame;
+        if next < adjacency[v].len() {
+            frame.2 += 1;
+            let (w, edge) = adjacency[v][next];
+            if edge == parent_edge || w == v {
+                continue;
+            }
+            if disc[w] == UNSEEN {
+                time += 1;
+                disc[w] = time;
+                low[w] = time;
+                edge_stack.push((v, w));
+                frames.push((w, edge, 0));
+            } else if disc[w] < disc[v] {
+                edge_stack.push((v, w));
+                low[v] = low[v].min(disc[w]);
+            }
+            continue;
+        }
+        frames.pop();
+        if let Some(&(u, _, _)) = frames.last() {
+            low[u] = low[u].min(low[v]);
+            if low[v] >= disc[u] {
+                // the edges above (u, v) form one biconnected component
+                while let Some((a, b)) = edge_stack.pop() {
+                    if u == root {
+                        component[a] = true;
+                        component[b] = true;
+                    }
+                    if (a, b) == (u, v) {
+                        break;
+                    }
+                }
+                if u == root {
+                    // the first child of the root was reached through the root's first edge
+                    break;
+                }
+            }
+        }
+    }
+    component
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{analysis::analyse, Percolation};
+
+    #[test]
+    fn spanning_cluster_analysis_works() {
+        let mut id = Percolation::new(4);
+        assert!(analyse(&id).is_none());
+        for (row, col) in [
+            (1, 1),
+            (1, 2),
+            (2, 1),
+            (2, 2),
+            (3, 2),
+            (3, 3),
+            (3, 4),
+            (4, 2),
+        ] {
+            id.open(row, col);
+        }
+        let cluster = analyse(&id).unwrap();
+        assert_eq!(cluster.chemical_distance, 3);
+        let mut backbone = cluster.backbone;
+        backbone.sort();
+        assert_eq!(
+            backbone,
+            vec![(1, 1), (1, 2), (2, 1), (2, 2), (3, 2), (4, 2)]
+        );
+        assert_eq!(cluster.dangling_ends, vec![(3, 3), (3, 4)]);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::{OpenOutcome, Percolation, CLOSE};
+use rand::Rng;
+use std::ops::Range;
+
+impl Percolation {
+    // opens every (row, col) in `sites` and returns how many of them were closed
+    pub fn open_many<I: IntoIterator<Item = (usize, usize)>>(&mut self, sites: I) -> usize {
+        sites
+            .into_iter()
+            .filter(|&(row, col)| self.open(row, col).opened)
+            .count()
+    }
+    // opens the block of rows `rows` and columns `cols`, both 1-based and end exclusive
+    pub fn open_rect(&mut self, rows: Range<usize>, cols: Range<usize>) -> usize {
+        self.open_many(rows.flat_map(|row| cols.clone().map(move |col| (row, col))))
+    }
+    // opens a closed site chosen uniformly at random, or returns None if every site is open
+    pub fn open_random<R: Rng + ?Sized>(
+        &mut self,
+        rng: &mut R,
+    ) -> Option<((usize, usize), OpenOutcome)> {
+        let closed = self.open.len() - self.nopen;
+        if closed == 0 {
+            return None;
+        }
+        if self.sites.is_empty() {
+            self.sites = (0..self.open.len()).collect();
+            self.sites.sort_by_key(|&index| self.open[index] != CLOSE);
+            self.slot = vec![0; self.open.len()];
+            for (slot, &index) in self.sites.iter().enumerate() {
+                self.slot[index] = slot;
+            }
+        }
+        let (row, col) = self.coordinates(self.sites[rng.gen_range(0..closed)]);
+        Some(((row, col), self.open(row, col)))
+    }
+    // opens random sites until the system percolates and returns the number of open sites
+    pub fn open_until_percolates<R: Rng + ?Sized>(&mut self, rng: &mut R) -> usize {
+        while !self.percolates && self.open_random(rng).is_some() {}
+        self.nopen
+    }
+    // Swaps `index` with the last of the closed sites at the front of `sites`. Called as a site
+    // is opened this shuffles the permutation one step further, and called as the opening is
+    // rolled back it undoes that step.
+    pub(crate) fn swap_boundary(&mut self, index: usize) {
+        if !self.sites.is_empty() {
+            self.swap_sites(index, self.sites[self.open.len() - self.nopen - 1]);
+        }
+    }
+    fn swap_sites(&mut self, index1: usize, index2: usize) {
+        let (slot1, slot2) = (self.slot[index1], self.slot[index2]);
+        self.sites.swap(slot1, slot2);
+        self.slot[index1] = slot2;
+        self.slot[index2] = slot1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Percolation;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn bulk_opening_works() {
+        let mut id = Percolation::new(4);
+        assert_eq!(id.open_rect(2..4, 1..5), 8);
+        assert_eq!(id.open_many([(2, 1), (1, 1), (4, 4)]), 2);
+        assert!(id.is_open(3, 4) && !id.is_open(4, 1));
+        assert!(id.percolates());
+        let mut rng = StdRng::seed_from_u64(40);
+        let mut id = Percolation::new(10);
+        let opened = id.open_until_percolates(&mut rng);
+        assert!(id.percolates());
+        assert_eq!(opened, id.number_of_open_sites());
+        id.undo();
+        id.close(1, 1);
+        while id.open_random(&mut rng).is_some() {}
+        assert_eq!(id.number_of_open_sites(), 100);
+    }
+}
@@ -0,0 +1,103 @@
+use crate::{Percolation, CLOSE};
+use std::collections::BTreeMap;
+
+// Order parameters of the clusters of open sites.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ClusterStats {
+    pub clusters: usize,
+    pub largest: usize,
+    // number of clusters of each size
+    pub histogram: BTreeMap<usize, usize>,
+    // sum of s^2 n_s over sum of s n_s, leaving out the spanning clusters
+    pub mean_size: f64,
+    // share of all sites that belong to a spanning cluster
+    pub spanning_fraction: f64,
+}
+
+impl ClusterStats {
+    // `spanning` holds the sizes of the clusters connecting the sources to the sinks
+    pub fn from_sizes<I: IntoIterator<Item = usize>>(
+        sizes: I,
+        spanning: &[usize],
+        sites: usize,
+    ) -> Self {
+        let mut stats = ClusterStats::default();
+        for size in sizes {
+            stats.clusters += 1;
+            stats.largest = stats.largest.max(size);
+            *stats.histogram.entry(size).or_insert(0) += 1;
+        }
+        let mut finite = stats.histogram.clone();
+        for size in spanning {
+            if let Some(count) = finite.get_mut(size) {
+                *count -= 1;
+            }
+        }
+        let (moment1, moment2) = finite.iter().fold((0, 0), |(m1, m2), (&size, &count)| {
+            (m1 + size * count, m2 + size * size * count)
+        });
+        if moment1 > 0 {
+            stats.mean_size = moment2 as f64 / moment1 as f64;
+        }
+        if sites > 0 {
+            stats.spanning_fraction = spanning.iter().sum::<usize>() as f64 / sites as f64;
+        }
+        stats
+    }
+}
+
+impl Percolation {
+    // closed sites are never joined to anything, so each of them is a component of its own
+    pub fn number_of_clusters(&self) -> usize {
+        self.id.count() - (self.open.len() - self.nopen)
+    }
+    pub fn cluster_stats(&self) -> ClusterStats {
+        let roots: Vec<usize> = (0..self.open.len())
+            .filter(|&index| self.open[index] != CLOSE && self.id.parent(index) == index)
+            .collect();
+        let spanning: Vec<usize> = roots
+            .iter()
+            .filter(|&&root| self.open[root] & self.source != 0 && self.open[root] & self.sink != 0)
+            .map(|&root| self.id.size(root))
+            .collect();
+        ClusterStats::from_sizes(
+            roots.iter().map(|&root| self.id.size(root)),
+            &spanning,
+            self.open.len(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Percolation;
+
+    #[test]
+    fn cluster_stats_work() {
+        let mut id = Percolation::new(4);
+        assert_eq!(id.number_of_clusters(), 0);
+        for (row, col) in [
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (4, 1),
+            (1, 3),
+            (1, 4),
+            (3, 3),
+            (4, 4),
+        ] {
+            id.open(row, col);
+        }
+        let stats = id.cluster_stats();
+        assert_eq!(id.number_of_clusters(), 4);
+        assert_eq!(stats.clusters, 4);
+        assert_eq!(stats.largest, 4);
+        assert_eq!(
+            stats.histogram.into_iter().collect::<Vec<_>>(),
+            vec![(1, 2), (2, 1), (4, 1)]
+        );
+        // (1 + 1 + 4) / (1 + 1 + 2) without the spanning cluster of 4 sites
+        assert_eq!(stats.mean_size, 1.5);
+        assert_eq!(stats.spanning_fraction, 0.25);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::PercolationError;
+
+// set on the entry of a site that is not a root, the other bits are then its parent
+const PARENT: u32 = 1 << 31;
+// the entry of a root holds its rank and whether its cluster touches the top and bottom rows
+const RANK: u32 = 0b11111;
+const TOP: u32 = 1 << 5;
+const BOTTOM: u32 = 1 << 6;
+
+// Top to bottom percolation on a free grid in 4.125 bytes per site: one u32 union-find entry
+// and one open bit, against the 17 bytes per site of `Percolation` before its undo log. It
+// has no undo history, sides or sources, and both vectors start zeroed so that the pages of a
+// large grid are only committed once sites in them are opened. Grids are limited to 2^31 sites.
+pub struct CompactPercolation {
+    length: usize,
+    nopen: usize,
+    id: Vec<u32>,
+    open: Vec<u64>,
+    percolates: bool,
+}
+
+impl CompactPercolation {
+    pub fn new(n: usize) -> Self {
+        match CompactPercolation::try_new(n) {
+            Ok(perc) => perc,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    pub fn try_new(n: usize) -> Result<Self, PercolationError> {
+        match n.checked_mul(n) {
+            Some(0) => Err(PercolationError::EmptyGrid),
+            Some(size) if size <= PARENT as usize => Ok(CompactPercolation {
+                length: n,
+                nopen: 0,
+                id: vec![0; size],
+                open: vec![0; size.div_ceil(64)],
+                percolates: false,
+            }),
+            _ => Err(PercolationError::TooLarge { n }),
+        }
+    }
+    fn adjust(&self, row: usize, col: usize) -> usize {
+        if row > self.length || col > self.length || row < 1 || col < 1 {
+            let n = self.length;
+            panic!("{}", PercolationError::OutOfBounds { row, col, n });
+        }
+        (row - 1) * self.length + col - 1
+    }
+    fn is_open_index(&self, index: usize) -> bool {
+        self.open[index / 64] & (1 << (index % 64)) != 0
+    }
+    fn find(&self, mut index: usize) -> usize {
+        while self.id[index] & PARENT != 0 {
+            index = (self.id[index] & !PARENT) as usize;
+        }
+        index
+    }
+    fn union(&mut self, index1: usize, index2: usize) {
+        let (mut root1, mut root2) = (self.find(index1), self.find(index2));
+        if root1 == root2 {
+            return;
+        }
+        if self.id[root1] & RANK < self.id[root2] & RANK {
+            std::mem::swap(&mut root1, &mut root2);
+        }
+        let (entry1, entry2) = (self.id[root1], self.id[root2]);
+        let grows = (entry1 & RANK == entry2 & RANK) as u32;
+        self.id[root1] = ((entry1 | entry2) & (TOP | BOTTOM)) | ((entry1 & RANK) + grows);
+        self.id[root2] = PARENT | root1 as u32;
+    }
+    pub fn open(&mut self, row: usize, col: usize) {
+        let index = self.adjust(row, col);
+        if self.is_open_index(index) {
+            return;
+        }
+        self.open[index / 64] |= 1 << (index % 64);
+        self.nopen += 1;
+        let n = self.length;
+        if row == 1 {
+            self.id[index] |= TOP;
+        }
+        if row == n {
+            self.id[index] |= BOTTOM;
+        }
+        let (row, col) = (row - 1, col - 1);
+        let neighbours = [
+            (col >= 1).then(|| index - 1),
+            (col + 1 < n).then(|| index + 1),
+            (row >= 1).then(|| index - n),
+            (row + 1 < n).then(|| index + n),
+        ];
+        for idxnear in neighbours.into_iter().flatten() {
+            if self.is_open_index(idxnear) {
+                self.union(index, idxnear);
+            }
+        }
+        if self.id[self.find(index)] & (TOP | BOTTOM) == TOP | BOTTOM {
+            self.percolates = true;
+        }
+    }
+    pub fn is_open(&self, row: usize, col: usize) -> bool {
+        self.is_open_index(self.adjust(row, col))
+    }
+    pub fn is_full(&self, row: usize, col: usize) -> bool {
+        let index = self.adjust(row, col);
+        self.is_open_index(index) && self.id[self.find(index)] & TOP != 0
+    }
+    pub fn number_of_open_sites(&self) -> usize {
+        self.nopen
+    }
+    pub fn percolates(&self) -> bool {
+        self.percolates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        compact::CompactPercolation,
+        tests::{open_alongside, Grid},
+    };
+
+    impl Grid for CompactPercolation {
+        fn open(&mut self, row: usize, col: usize) {
+            self.open(row, col);
+        }
+        fn is_open(&self, row: usize, col: usize) -> bool {
+            self.is_open(row, col)
+        }
+        fn number_of_open_sites(&self) -> usize {
+            self.number_of_open_sites()
+        }
+        fn percolates(&self) -> bool {
+            self.percolates()
+        }
+    }
+
+    #[test]
+    fn compact_percolation_agrees() {
+        let n = 30;
+        let mut compact = CompactPercolation::new(n);
+        let id = open_alongside(&mut compact, n, 42);
+        for row in 1..=n {
+            for col in 1..=n {
+                assert_eq!(compact.is_full(row, col), id.is_full(row, col));
+            }
+        }
+    }
+
+    #[test]
+    fn compact_percolation_fits_a_large_grid() {
+        let n = 20_000;
+        let mut id = CompactPercolation::new(n);
+        for row in 1..=n {
+            id.open(row, n / 2);
+        }
+        assert!(id.percolates());
+        assert!(id.is_full(n, n / 2) && !id.is_open(n, 1));
+        assert_eq!(id.number_of_open_sites(), n);
+    }
+}
@@ -0,0 +1,192 @@
+const CLOSE: u8 = 0;
+const OPEN: u8 = 1;
+const FULL: u8 = 2;
+
+use crate::PercolationError;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Flow {
+    // the square lattice turned by 45 degrees, whose site threshold is about 0.6447: odd rows
+    // sit half a site to the right of even ones and fluid moves to the two sites below it that
+    // it touches, (row + 1, col) and (row + 1, col + 1) from an even row, or (row + 1, col - 1)
+    // and (row + 1, col) from an odd row, counting rows from 0
+    Diagonal,
+    // to the sites below, to the left and to the right
+    Sideways,
+}
+
+// Fluid enters through the top row and can only move as `flow` allows, so fullness has to be
+// flooded forward from the sites it reaches instead of being read off a union-find root.
+pub struct DirectedPercolation {
+    length: usize,
+    nopen: usize,
+    flow: Flow,
+    open: Vec<u8>,
+    percolates: bool,
+}
+
+impl DirectedPercolation {
+    pub fn new(n: usize, flow: Flow) -> Self {
+        match DirectedPercolation::try_new(n, flow) {
+            Ok(perc) => perc,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    pub fn try_new(n: usize, flow: Flow) -> Result<Self, PercolationError> {
+        match n.checked_mul(n) {
+            Some(0) => Err(PercolationError::EmptyGrid),
+            Some(size) => Ok(DirectedPercolation {
+                length: n,
+                nopen: 0,
+                flow,
+                open: vec![CLOSE; size],
+                percolates: false,
+            }),
+            None => Err(PercolationError::TooLarge { n }),
+        }
+    }
+    fn try_adjust(&self, row: usize, col: usize) -> Result<(usize, usize), PercolationError> {
+        if row > self.length || col > self.length || row < 1 || col < 1 {
+            let n = self.length;
+            return Err(PercolationError::OutOfBounds { row, col, n });
+        }
+        Ok((row - 1, col - 1))
+    }
+    fn adjust(&self, row: usize, col: usize) -> (usize, usize) {
+        match self.try_adjust(row, col) {
+            Ok(coordinates) => coordinates,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    fn index(&self, coordinates: (usize, usize)) -> usize {
+        coordinates.0 * self.length + coordinates.1
+    }
+    fn left(&self, index: usize) -> Option<usize> {
+        (index % self.length >= 1).then(|| index - 1)
+    }
+    fn right(&self, index: usize) -> Option<usize> {
+        (index % self.length + 1 < self.length).then(|| index + 1)
+    }
+    // the site in the same column of the row below or above, then the diagonal or sideways ones
+    fn neighbours(&self, index: usize, vertical: Option<usize>) -> [Option<usize>; 3] {
+        match self.flow {
+            Flow::Diagonal => {
+                let shifted = vertical.and_then(|idx| {
+                    // an even row reaches right into the odd rows and an odd row left
+                    if (index / self.length).is_multiple_of(2) {
+                        self.right(idx)
+                    } else {
+                        self.left(idx)
+                    }
+                });
+                [vertical, shifted, None]
+            }
+            Flow::Sideways => [vertical, self.left(index), self.right(index)],
+        }
+    }
+    // sites the fluid can move into from `index`
+    fn downstream(&self, index: usize) -> impl Iterator<Item = usize> {
+        let below = (index + self.length < self.open.len()).then(|| index + self.length);
+        self.neighbours(index, below).into_iter().flatten()
+    }
+    // sites the fluid can arrive at `index` from
+    fn upstream(&self, index: usize) -> impl Iterator<Item = usize> {
+        let above = (index >= self.length).then(|| index - self.length);
+        self.neighbours(index, above).into_iter().flatten()
+    }
+    fn fill(&mut self, index: usize) {
+        let mut stack = vec![index];
+        self.open[index] |= FULL;
+        while let Some(index) = stack.pop() {
+            if index / self.length == self.length - 1 {
+                self.percolates = true;
+            }
+            for next in self.downstream(index) {
+                if self.open[next] == OPEN {
+                    self.open[next] |= FULL;
+                    stack.push(next);
+                }
+            }
+        }
+    }
+    pub fn open(&mut self, row: usize, col: usize) {
+        let (row, col) = self.adjust(row, col);
+        let index = self.index((row, col));
+        if self.open[index] == CLOSE {
+            self.open[index] = OPEN;
+            self.nopen += 1;
+            if row == 0 || self.upstream(index).any(|idx| self.open[idx] & FULL != 0) {
+                self.fill(index);
+            }
+        }
+    }
+    pub fn try_open(&mut self, row: usize, col: usize) -> Result<(), PercolationError> {
+        self.try_adjust(row, col)?;
+        self.open(row, col);
+        Ok(())
+    }
+    pub fn is_open(&self, row: usize, col: usize) -> bool {
+        self.open[self.index(self.adjust(row, col))] != CLOSE
+    }
+    pub fn is_full(&self, row: usize, col: usize) -> bool {
+        self.open[self.index(self.adjust(row, col))] & FULL != 0
+    }
+    pub fn number_of_open_sites(&self) -> usize {
+        self.nopen
+    }
+    pub fn percolates(&self) -> bool {
+        self.percolates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        directed::{DirectedPercolation, Flow},
+        PercolationError,
+    };
+
+    #[test]
+    fn directed_percolation_works() {
+        for flow in [Flow::Diagonal, Flow::Sideways] {
+            let mut id = DirectedPercolation::new(3, flow);
+            id.open(3, 2);
+            id.open(2, 2);
+            id.open(2, 1);
+            assert!(!id.is_full(2, 1));
+            id.open(1, 1);
+            assert!(id.is_full(2, 1) && id.is_full(2, 2));
+            assert!(id.percolates());
+        }
+        // fluid moves diagonally on the diagonal lattice and sideways otherwise
+        for (flow, diagonal) in [(Flow::Diagonal, true), (Flow::Sideways, false)] {
+            let mut id = DirectedPercolation::new(3, flow);
+            id.open(1, 1);
+            id.open(2, 2);
+            assert_eq!(id.is_full(2, 2), diagonal);
+            id.open(3, 1);
+            assert_eq!(id.percolates(), diagonal);
+            id.open(2, 1);
+            id.open(2, 3);
+            assert_eq!(id.is_full(2, 3), !diagonal);
+        }
+        // fluid that reaches the bottom cannot climb back up
+        let mut id = DirectedPercolation::new(3, Flow::Sideways);
+        id.open(1, 3);
+        id.open(2, 3);
+        id.open(3, 3);
+        id.open(3, 2);
+        id.open(2, 1);
+        id.open(3, 1);
+        assert!(id.percolates());
+        assert!(id.is_full(3, 1));
+        assert!(!id.is_full(2, 1));
+        let out_of_bounds = PercolationError::OutOfBounds {
+            row: 4,
+            col: 1,
+            n: 3,
+        };
+        assert_eq!(id.try_open(4, 1), Err(out_of_bounds));
+        assert!(DirectedPercolation::try_new(0, Flow::Diagonal).is_err());
+    }
+}
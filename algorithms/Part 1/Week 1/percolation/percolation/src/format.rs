@@ -0,0 +1,463 @@
+use crate::{Percolation, PercolationError};
+use std::{collections::HashMap, error::Error, fmt, io::BufRead, iter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    // one line per row with `#` for an open site and `.` for a closed one
+    Ascii,
+    // one line per row with 1 for an open site and 0 for a closed one
+    Matrix,
+    // the grid size, then the row and column of every site to open, as the visualizers read
+    Sequence,
+}
+
+// a grid size and the 1-based (row, col) of the sites to open, in order
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PercolationInput {
+    pub n: usize,
+    pub sites: Vec<(usize, usize)>,
+    // problems `read_input` let through, in the order they appear
+    pub warnings: Vec<Warning>,
+}
+
+impl PercolationInput {
+    pub fn from_percolation(perc: &Percolation) -> Self {
+        PercolationInput {
+            n: perc.grid_size(),
+            sites: perc.open_sites().collect(),
+            warnings: Vec::new(),
+        }
+    }
+    pub fn to_percolation(&self) -> Percolation {
+        let mut perc = Percolation::new(self.n);
+        perc.open_many(self.sites.iter().copied());
+        perc
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new<S: Into<String>>(line: usize, column: usize, message: S) -> Self {
+        ParseError {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WarningKind {
+    // the site is left out of `sites`
+    OutOfBounds,
+    // the site is kept, opening it again is harmless
+    Duplicate { first_line: usize },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Warning {
+    pub line: usize,
+    pub column: usize,
+    pub row: usize,
+    pub col: usize,
+    pub kind: WarningKind,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match self.kind {
+            WarningKind::OutOfBounds => {
+                write!(f, "({},{}) is outside the grid", self.row, self.col)
+            }
+            WarningKind::Duplicate { first_line } => write!(
+                f,
+                "({},{}) was already opened on line {}",
+                self.row, self.col, first_line
+            ),
+        }
+    }
+}
+
+// whitespace separated tokens of a line with the 1-based column they start at
+fn tokens(line: &str) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    for (i, c) in line.char_indices().chain(iter::once((line.len(), ' '))) {
+        match (start, c.is_whitespace()) {
+            (None, false) => start = Some(i),
+            (Some(s), true) => {
+                tokens.push((s + 1, &line[s..i]));
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    tokens
+}
+
+// A text is a grid of `#` and `.` if it has nothing else, a 0/1 matrix if every line is a row of
+// 0s and 1s as wide as there are lines, and a sequence otherwise. A lone `0` can only be a
+// matrix, but a lone `1` is both a 1 by 1 matrix and a grid of size 1 with nothing opened and
+// reads as the latter; use `parse_as` for the former.
+pub fn detect(text: &str) -> Format {
+    let rows: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect();
+    let cells = |row: &str| {
+        row.chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<Vec<_>>()
+    };
+    let only = |allowed: [char; 2]| {
+        rows.iter()
+            .all(|row| cells(row).iter().all(|c| allowed.contains(c)))
+    };
+    if !rows.is_empty() && only(['#', '.']) {
+        Format::Ascii
+    } else if (rows.len() > 1 || rows.len() == 1 && cells(rows[0]) == ['0'])
+        && only(['0', '1'])
+        && rows.iter().all(|row| cells(row).len() == rows.len())
+    {
+        Format::Matrix
+    } else {
+        Format::Sequence
+    }
+}
+
+pub fn parse(text: &str) -> Result<PercolationInput, ParseError> {
+    parse_as(text, detect(text))
+}
+
+pub fn parse_as(text: &str, format: Format) -> Result<PercolationInput, ParseError> {
+    match format {
+        Format::Ascii => parse_grid(text, '#', '.'),
+        Format::Matrix => parse_grid(text, '1', '0'),
+        Format::Sequence => parse_sequence(text),
+    }
+}
+
+fn parse_grid(text: &str, open: char, closed: char) -> Result<PercolationInput, ParseError> {
+    let mut input = PercolationInput::default();
+    let mut row = 0;
+    let mut last = 0;
+    for (number, line) in text.lines().enumerate() {
+        let cells: Vec<(usize, char)> = line
+            .char_indices()
+            .filter(|(_, c)| !c.is_whitespace())
+            .collect();
+        if cells.is_empty() {
+            continue;
+        }
+        row += 1;
+        last = number + 1;
+        if row == 1 {
+            input.n = cells.len();
+        }
+        if cells.len() != input.n {
+            let column = cells.get(input.n).map_or(line.len(), |&(i, _)| i) + 1;
+            let message = format!("expected {} sites, found {}", input.n, cells.len());
+            return Err(ParseError::new(number + 1, column, message));
+        }
+        for (col, &(i, c)) in cells.iter().enumerate() {
+            if c == open {
+                input.sites.push((row, col + 1));
+            } else if c != closed {
+                let message = format!("expected {:?} or {:?}, found {:?}", open, closed, c);
+                return Err(ParseError::new(number + 1, i + 1, message));
+            }
+        }
+    }
+    if row == 0 {
+        return Err(ParseError::new(
+            1,
+            1,
+            PercolationError::EmptyGrid.to_string(),
+        ));
+    }
+    if row != input.n {
+        let message = format!("expected {} rows, found {}", input.n, row);
+        return Err(ParseError::new(last + 1, 1, message));
+    }
+    Ok(input)
+}
+
+fn parse_sequence(text: &str) -> Result<PercolationInput, ParseError> {
+    read_sequence(text.as_bytes(), true)
+}
+
+// Reads a sequence one line at a time, as the visualizers do, reporting sites outside the grid
+// and sites opened twice as warnings instead of failing on them.
+pub fn read_input<R: BufRead>(reader: R) -> Result<PercolationInput, ParseError> {
+    read_sequence(reader, false)
+}
+
+fn read_sequence<R: BufRead>(reader: R, strict: bool) -> Result<PercolationInput, ParseError> {
+    let number = |line: usize, column: usize, token: &str| {
+        token.parse::<usize>().map_err(|_| {
+            ParseError::new(
+                line,
+                column,
+                format!("expected a number, found {:?}", token),
+            )
+        })
+    };
+    let mut input = PercolationInput::default();
+    let mut opened = HashMap::new();
+    // the row of a site whose column has not been read yet, with where it starts
+    let mut pending = None;
+    let mut last = 0;
+    for (index, text) in reader.lines().enumerate() {
+        let line = index + 1;
+        let text = text.map_err(|e| ParseError::new(line, 1, e.to_string()))?;
+        last = line;
+        for (column, token) in tokens(&text) {
+            let value = number(line, column, token)?;
+            if input.n == 0 {
+                if value == 0 {
+                    let message = PercolationError::EmptyGrid.to_string();
+                    return Err(ParseError::new(line, column, message));
+                }
+                input.n = value;
+                continue;
+            }
+            let (line, column, row) = match pending.take() {
+                Some(start) => start,
+                None => {
+                    pending = Some((line, column, value));
+                    continue;
+                }
+            };
+            let (col, n) = (value, input.n);
+            let warning = |kind| Warning {
+                line,
+                column,
+                row,
+                col,
+                kind,
+            };
+            if row < 1 || row > n || col < 1 || col > n {
+                if strict {
+                    let message = PercolationError::OutOfBounds { row, col, n }.to_string();
+                    return Err(ParseError::new(line, column, message));
+                }
+                input.warnings.push(warning(WarningKind::OutOfBounds));
+                continue;
+            }
+            if !strict {
+                match opened.get(&(row, col)) {
+                    Some(&first_line) => input
+                        .warnings
+                        .push(warning(WarningKind::Duplicate { first_line })),
+                    None => {
+                        opened.insert((row, col), line);
+                    }
+                }
+            }
+            input.sites.push((row, col));
+        }
+    }
+    if input.n == 0 {
+        return Err(ParseError::new(last.max(1), 1, "missing grid size"));
+    }
+    if let Some((line, column, _)) = pending {
+        return Err(ParseError::new(line, column, "row without a column"));
+    }
+    Ok(input)
+}
+
+// grids only keep which sites are open, so writing one loses the order and any repeats
+pub fn write(input: &PercolationInput, format: Format) -> Result<String, PercolationError> {
+    let n = input.n;
+    if n == 0 {
+        return Err(PercolationError::EmptyGrid);
+    }
+    if let Some(&(row, col)) = input
+        .sites
+        .iter()
+        .find(|&&(row, col)| row < 1 || row > n || col < 1 || col > n)
+    {
+        return Err(PercolationError::OutOfBounds { row, col, n });
+    }
+    if format == Format::Sequence {
+        let mut text = format!("{}\n", n);
+        for &(row, col) in &input.sites {
+            text += &format!("{} {}\n", row, col);
+        }
+        return Ok(text);
+    }
+    let mut open = vec![false; n * n];
+    for &(row, col) in &input.sites {
+        open[(row - 1) * n + col - 1] = true;
+    }
+    let (opened, closed, separator) = match format {
+        Format::Ascii => ("#", ".", ""),
+        _ => ("1", "0", " "),
+    };
+    let mut text = String::new();
+    for row in open.chunks(n) {
+        let cells: Vec<&str> = row
+            .iter()
+            .map(|&open| if open { opened } else { closed })
+            .collect();
+        text += &cells.join(separator);
+        text.push('\n');
+    }
+    Ok(text)
+}
+
+pub fn convert(text: &str, format: Format) -> Result<String, ParseError> {
+    let input = parse(text)?;
+    write(&input, format).map_err(|e| ParseError::new(1, 1, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        format::{
+            convert, detect, parse, parse_as, read_input, write, Format, ParseError,
+            PercolationInput, WarningKind,
+        },
+        PercolationError,
+    };
+    use std::fs;
+
+    #[test]
+    fn formats_convert() {
+        let ascii = "#..\n.##\n.#.\n";
+        assert_eq!(detect(ascii), Format::Ascii);
+        let input = parse(ascii).unwrap();
+        assert_eq!(input.sites, vec![(1, 1), (2, 2), (2, 3), (3, 2)]);
+        assert!(!input.to_percolation().percolates());
+        let matrix = convert(ascii, Format::Matrix).unwrap();
+        assert_eq!(matrix, "1 0 0\n0 1 1\n0 1 0\n");
+        assert_eq!(detect(&matrix), Format::Matrix);
+        let sequence = convert(&matrix, Format::Sequence).unwrap();
+        assert_eq!(sequence, "3\n1 1\n2 2\n2 3\n3 2\n");
+        assert_eq!(detect(&sequence), Format::Sequence);
+        assert_eq!(
+            write(&parse(&sequence).unwrap(), Format::Ascii).unwrap(),
+            ascii
+        );
+    }
+
+    #[test]
+    fn single_sites_convert() {
+        let closed = convert("1\n", Format::Matrix).unwrap();
+        assert_eq!(closed, "0\n");
+        assert_eq!(detect(&closed), Format::Matrix);
+        assert_eq!(parse(&closed).unwrap().sites, vec![]);
+        // a lone 1 is read as a grid of size 1, so an open 1 by 1 matrix needs `parse_as`
+        let open = convert("1\n1 1\n", Format::Matrix).unwrap();
+        assert_eq!(open, "1\n");
+        assert_eq!(detect(&open), Format::Sequence);
+        assert_eq!(parse_as(&open, Format::Matrix).unwrap().sites, vec![(1, 1)]);
+        assert_eq!(convert("#\n", Format::Sequence).unwrap(), "1\n1 1\n");
+    }
+
+    #[test]
+    fn write_checks_sites() {
+        let input = |n, sites| PercolationInput {
+            n,
+            sites,
+            warnings: Vec::new(),
+        };
+        assert_eq!(
+            write(&input(2, vec![(1, 1), (2, 3)]), Format::Ascii),
+            Err(PercolationError::OutOfBounds {
+                row: 2,
+                col: 3,
+                n: 2
+            })
+        );
+        assert_eq!(
+            write(&input(2, vec![(0, 1)]), Format::Sequence),
+            Err(PercolationError::OutOfBounds {
+                row: 0,
+                col: 1,
+                n: 2
+            })
+        );
+        assert_eq!(
+            write(&input(0, vec![]), Format::Matrix),
+            Err(PercolationError::EmptyGrid)
+        );
+    }
+
+    #[test]
+    fn parse_errors_have_positions() {
+        let error = |line, column, message: &str| {
+            Err(ParseError {
+                line,
+                column,
+                message: message.to_string(),
+            })
+        };
+        assert_eq!(
+            parse_as("#.\n#x\n", Format::Ascii),
+            error(2, 2, "expected '#' or '.', found 'x'")
+        );
+        assert_eq!(
+            parse("3\n1 1\n 2 x\n"),
+            error(3, 4, "expected a number, found \"x\"")
+        );
+        assert_eq!(
+            parse("2\n1 3\n"),
+            error(2, 1, "Invalid (row, col): (1,3) in a grid of size 2")
+        );
+        assert_eq!(parse("2\n1 1\n2"), error(3, 1, "row without a column"));
+    }
+
+    #[test]
+    fn read_input_warns_about_bad_sites() {
+        let input = read_input("3\n1 1\n4 2  2 2\n1\n 1\n".as_bytes()).unwrap();
+        assert_eq!(input.sites, vec![(1, 1), (2, 2), (1, 1)]);
+        let warnings: Vec<_> = input
+            .warnings
+            .iter()
+            .map(|w| (w.line, w.column, w.kind))
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                (3, 1, WarningKind::OutOfBounds),
+                (4, 1, WarningKind::Duplicate { first_line: 2 })
+            ]
+        );
+        assert_eq!(
+            input.warnings[1].to_string(),
+            "line 4, column 1: (1,1) was already opened on line 2"
+        );
+        assert_eq!(
+            read_input("".as_bytes()).unwrap_err().message,
+            "missing grid size"
+        );
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../percolationvisualizer/inputs/input8-dups.txt"
+        );
+        let text = fs::read_to_string(path).unwrap();
+        let input = read_input(text.as_bytes()).unwrap();
+        assert!(!input.warnings.is_empty());
+        assert_eq!(input.sites, parse(&text).unwrap().sites);
+    }
+}
@@ -0,0 +1,129 @@
+use weightedquickunion::WeightedQuickUnionUF;
+
+const CLOSE: u8 = 0;
+const OPEN: u8 = 1;
+const SOURCE: u8 = 2;
+const SINK: u8 = 4;
+
+// Percolation on an arbitrary graph: nodes are opened one at a time, a node is full when an
+// open path joins it to a source node, and the system percolates once a sink node is full.
+// Nodes are numbered from 0 and every edge is followed in both directions.
+pub struct GraphPercolation {
+    nopen: usize,
+    id: WeightedQuickUnionUF,
+    open: Vec<u8>,
+    adjacency: Vec<Vec<usize>>,
+    percolates: bool,
+}
+
+impl GraphPercolation {
+    pub fn new<S, T>(adjacency: Vec<Vec<usize>>, sources: S, sinks: T) -> Self
+    where
+        S: IntoIterator<Item = usize>,
+        T: IntoIterator<Item = usize>,
+    {
+        let nodes = adjacency.len();
+        let edges = adjacency
+            .into_iter()
+            .enumerate()
+            .flat_map(|(node, near)| near.into_iter().map(move |other| (node, other)));
+        GraphPercolation::from_edges(nodes, edges, sources, sinks)
+    }
+    pub fn from_edges<E, S, T>(nodes: usize, edges: E, sources: S, sinks: T) -> Self
+    where
+        E: IntoIterator<Item = (usize, usize)>,
+        S: IntoIterator<Item = usize>,
+        T: IntoIterator<Item = usize>,
+    {
+        let mut perc = GraphPercolation {
+            nopen: 0,
+            id: WeightedQuickUnionUF::new(nodes),
+            open: vec![CLOSE; nodes],
+            adjacency: vec![Vec::new(); nodes],
+            percolates: false,
+        };
+        for (node1, node2) in edges {
+            perc.validate(node1);
+            perc.validate(node2);
+            if !perc.adjacency[node1].contains(&node2) {
+                perc.adjacency[node1].push(node2);
+                perc.adjacency[node2].push(node1);
+            }
+        }
+        for node in sources {
+            perc.validate(node);
+            perc.open[node] |= SOURCE;
+        }
+        for node in sinks {
+            perc.validate(node);
+            perc.open[node] |= SINK;
+        }
+        perc
+    }
+    fn validate(&self, node: usize) {
+        if node >= self.open.len() {
+            panic!("Invalid node: {}", node);
+        }
+    }
+    pub fn open(&mut self, node: usize) {
+        self.validate(node);
+        if self.open[node] & OPEN != 0 {
+            return;
+        }
+        let mut status = self.open[node] | OPEN;
+        for &near in &self.adjacency[node] {
+            if self.open[near] & OPEN != 0 {
+                status |= self.open[self.id.find(near)];
+                self.id.union(node, near);
+            }
+        }
+        let root = self.id.find(node);
+        self.open[root] |= status;
+        self.open[node] |= OPEN;
+        if self.open[root] & SOURCE != 0 && self.open[root] & SINK != 0 {
+            self.percolates = true;
+        }
+        self.nopen += 1;
+    }
+    pub fn is_open(&self, node: usize) -> bool {
+        self.validate(node);
+        self.open[node] & OPEN != 0
+    }
+    pub fn is_full(&self, node: usize) -> bool {
+        self.is_open(node) && self.open[self.id.find(node)] & SOURCE != 0
+    }
+    pub fn number_of_open_sites(&self) -> usize {
+        self.nopen
+    }
+    pub fn percolates(&self) -> bool {
+        self.percolates
+    }
+    pub fn neighbours(&self, node: usize) -> &[usize] {
+        self.validate(node);
+        &self.adjacency[node]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::GraphPercolation;
+
+    #[test]
+    fn graph_percolation_works() {
+        // a triangle 0-1-2 with a tail 2-3-4, fed at 0 and drained at 4
+        let adjacency = vec![vec![1, 2], vec![2], vec![3], vec![4], vec![]];
+        let mut id = GraphPercolation::new(adjacency, [0], [4]);
+        id.open(4);
+        id.open(3);
+        id.open(1);
+        assert!(!id.is_full(1));
+        id.open(0);
+        assert!(id.is_full(1));
+        assert!(!id.percolates());
+        id.open(2);
+        assert!(id.is_full(4));
+        assert!(id.percolates());
+        assert_eq!(id.number_of_open_sites(), 5);
+        assert_eq!(id.neighbours(2), &[0, 1, 3]);
+    }
+}
@@ -1,11 +1,18 @@
 mod text;
 use crate::gui::text::{draw_text_mut, text_size};
-use crate::Percolation;
+use crate::{
+    format::{self, PercolationInput},
+    naive::NaiveVirtualSitesPercolation,
+    recording::RecordingPercolation,
+    OpenOutcome, Percolation, SiteState,
+};
 use font_kit::{handle::Handle, source::SystemSource};
 use image::{Rgb, RgbImage};
 use native_dialog::{FileDialog, MessageDialog};
 use rusttype::{Font, Scale};
+use std::collections::HashSet;
 use std::fs;
+use std::io::{self, Write};
 use std::num::ParseIntError;
 
 pub const LENGTH: usize = 512;
@@ -76,13 +83,54 @@ pub fn save_text(text_buf: &String) {
     }
 }
 
-pub fn draw_status_bar(perc: &Percolation, render: bool, font: &Font) -> Vec<u32> {
+pub fn save_snapshot(perc: &Percolation) {
+    if let Some(path) = FileDialog::new()
+        .set_location("~/Desktop")
+        .add_filter("Percolation Snapshot", &["perc"])
+        .show_save_single_file()
+        .unwrap()
+    {
+        let saved = fs::File::create(path).and_then(|file| {
+            let mut writer = io::BufWriter::new(file);
+            perc.write_snapshot(&mut writer)?;
+            writer.flush()
+        });
+        if let Err(e) = saved {
+            message_box("Unable to save session", e.to_string().as_str());
+        }
+    }
+}
+
+pub fn open_snapshot() -> Option<Percolation> {
+    let path = FileDialog::new()
+        .set_location("~/Desktop")
+        .add_filter("Percolation Snapshot", &["perc"])
+        .show_open_single_file()
+        .unwrap()?;
+    match fs::File::open(path).and_then(|file| Percolation::read_snapshot(io::BufReader::new(file)))
+    {
+        Ok(perc) => Some(perc),
+        Err(e) => {
+            message_box("Unable to resume session", e.to_string().as_str());
+            None
+        }
+    }
+}
+
+// `path_length` is the number of steps of a highlighted percolation path, if there is one
+pub fn draw_status_bar(
+    perc: &Percolation,
+    render: bool,
+    path_length: Option<usize>,
+    font: &Font,
+) -> Vec<u32> {
     let scale: Scale = Scale {
         x: STATUS_TEXT_HEIGHT,
         y: STATUS_TEXT_HEIGHT,
     };
     let nopen;
     let temp;
+    let path;
     let mut left = "";
     let mut right = "";
     if render {
@@ -93,7 +141,10 @@ pub fn draw_status_bar(perc: &Percolation, render: bool, font: &Font) -> Vec<u32
         } else {
             left = temp.as_str();
         }
-        if perc.percolates() {
+        if let Some(length) = path_length {
+            path = format!("percolates, path length {}", length);
+            right = path.as_str();
+        } else if perc.percolates() {
             right = "percolates";
         } else {
             right = "does not percolate";
@@ -134,22 +185,41 @@ pub fn draw_status_bar(perc: &Percolation, render: bool, font: &Font) -> Vec<u32
     statusbuffer
 }
 
-pub fn open() -> Vec<usize> {
-    if let Some(path) = FileDialog::new()
+// warnings are shown but do not stop the file from opening
+pub fn open() -> Option<PercolationInput> {
+    let path = FileDialog::new()
         .set_location("~/Desktop")
         .add_filter("Text File", &["txt"])
         .show_open_single_file()
-        .unwrap()
-    {
-        let input_file = fs::read_to_string(path)
-            .expect("Invalid file name. If not in current dir include the path");
-        return input_file
-            .trim()
-            .split_whitespace()
-            .map(|val| val.parse::<usize>().unwrap())
-            .collect::<Vec<_>>();
+        .unwrap()?;
+    let input = match fs::File::open(path) {
+        Ok(file) => format::read_input(io::BufReader::new(file)),
+        Err(e) => {
+            message_box("Unable to open input file", e.to_string().as_str());
+            return None;
+        }
+    };
+    match input {
+        Ok(input) => {
+            if !input.warnings.is_empty() {
+                let mut message: Vec<String> = input
+                    .warnings
+                    .iter()
+                    .take(10)
+                    .map(|w| w.to_string())
+                    .collect();
+                if input.warnings.len() > 10 {
+                    message.push(format!("and {} more", input.warnings.len() - 10));
+                }
+                message_box("Input file has problems", &message.join("\n"));
+            }
+            Some(input)
+        }
+        Err(e) => {
+            message_box("Invalid input file", e.to_string().as_str());
+            None
+        }
     }
-    vec![]
 }
 
 pub fn save_screen(buffer: &mut Vec<u32>) {
@@ -210,3 +280,126 @@ pub fn half_length(n: usize) -> usize {
         ),
     }
 }
+
+// draws the site opened with `outcome` and every site it filled
+pub fn fill_outcome(
+    idx: usize,
+    idy: usize,
+    outcome: &OpenOutcome,
+    n: usize,
+    half_length: usize,
+    colorbuffer: &mut Vec<u32>,
+) {
+    if outcome.opened && !outcome.became_full {
+        fill_rect(idx, idy, n, half_length, 16777215, colorbuffer);
+    }
+    for &(idy, idx) in &outcome.newly_full {
+        fill_rect(idx, idy, n, half_length, 6801139, colorbuffer); //rgb(103,198,243)
+    }
+}
+
+// redraws every open site, for changes that can empty sites as well as fill them
+pub fn fill_grid(perc: &Percolation, n: usize, half_length: usize, colorbuffer: &mut Vec<u32>) {
+    for ((idy, idx), state) in perc.cells() {
+        match state {
+            SiteState::Full => fill_rect(idx, idy, n, half_length, 6801139, colorbuffer),
+            SiteState::Open => fill_rect(idx, idy, n, half_length, 16777215, colorbuffer),
+            SiteState::Closed => (),
+        }
+    }
+}
+
+// draws the naive grid like `fill_grid`, with the sites only it reports full in red
+pub fn fill_backwash(
+    naive: &NaiveVirtualSitesPercolation,
+    perc: &Percolation,
+    n: usize,
+    half_length: usize,
+    colorbuffer: &mut Vec<u32>,
+) {
+    for ((idy, idx), state) in perc.cells() {
+        let color = match state {
+            SiteState::Closed => continue,
+            SiteState::Full => 6801139,
+            _ if naive.is_full(idy, idx) => 16711680, //rgb(255,0,0)
+            SiteState::Open => 16777215,
+        };
+        fill_rect(idx, idy, n, half_length, color, colorbuffer);
+    }
+}
+
+// Repaints the naive grid after `site` was opened in it, given whether it percolated before.
+// An opening that fills sites fills its whole open cluster, and the clusters touching the bottom
+// row as well when it makes the grid percolate, so only those are walked.
+pub fn fill_backwash_step(
+    naive: &NaiveVirtualSitesPercolation,
+    perc: &Percolation,
+    site: (usize, usize),
+    percolated: bool,
+    n: usize,
+    half_length: usize,
+    colorbuffer: &mut Vec<u32>,
+) {
+    let spread = naive.is_full(site.0, site.1);
+    let mut stack = vec![site];
+    if spread && !percolated && naive.percolates() {
+        stack.extend((1..=n).map(|col| (n, col)));
+    }
+    let mut seen = HashSet::new();
+    while let Some((idy, idx)) = stack.pop() {
+        if !naive.is_open(idy, idx) || !seen.insert((idy, idx)) {
+            continue;
+        }
+        let color = if perc.is_full(idy, idx) {
+            6801139
+        } else if naive.is_full(idy, idx) {
+            16711680
+        } else {
+            16777215
+        };
+        fill_rect(idx, idy, n, half_length, color, colorbuffer);
+        if spread {
+            stack.extend(
+                [
+                    (idy - 1, idx),
+                    (idy + 1, idx),
+                    (idy, idx - 1),
+                    (idy, idx + 1),
+                ]
+                .into_iter()
+                .filter(|&(row, col)| (1..=n).contains(&row) && (1..=n).contains(&col)),
+            );
+        }
+    }
+}
+
+// colors full sites from blue to red by the step at which they filled, open sites stay white
+pub fn fill_heatmap(
+    recording: &RecordingPercolation,
+    n: usize,
+    half_length: usize,
+    colorbuffer: &mut Vec<u32>,
+) {
+    let last = recording.percolation().number_of_open_sites().max(1);
+    for idx in 1..=n {
+        for idy in 1..=n {
+            match recording.filled_at(idy, idx) {
+                Some(step) => {
+                    let heat = (255 * step / last) as u32;
+                    fill_rect(
+                        idx,
+                        idy,
+                        n,
+                        half_length,
+                        (heat << 16) | (255 - heat),
+                        colorbuffer,
+                    );
+                }
+                None if recording.opened_at(idy, idx).is_some() => {
+                    fill_rect(idx, idy, n, half_length, 16777215, colorbuffer);
+                }
+                None => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{clusters::ClusterStats, PercolationError};
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead},
+    iter,
+};
+
+// Labels the clusters of an occupancy matrix that is fed in one row at a time, keeping only the
+// labels of the last row, so memory grows with the width of the grid and not with its height.
+// The first row is the top and the last row added so far is the bottom.
+pub struct HoshenKopelman {
+    length: usize,
+    rows: usize,
+    // labels of the last row, 0 for closed sites and 1.. for the clusters that reach it
+    labels: Vec<usize>,
+    // union-find over the labels in use, indexed by label
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    top: Vec<bool>,
+    // clusters that no longer reach the last row
+    histogram: BTreeMap<usize, usize>,
+}
+
+impl HoshenKopelman {
+    pub fn new(n: usize) -> Self {
+        HoshenKopelman {
+            length: n,
+            rows: 0,
+            labels: vec![0; n],
+            parent: vec![0],
+            size: vec![0],
+            top: vec![false],
+            histogram: BTreeMap::new(),
+        }
+    }
+    pub fn from_rows<I, R>(n: usize, rows: I) -> Self
+    where
+        I: IntoIterator<Item = R>,
+        R: AsRef<[bool]>,
+    {
+        let mut hk = HoshenKopelman::new(n);
+        for row in rows {
+            hk.push_row(row.as_ref());
+        }
+        hk
+    }
+    // reads a matrix with one row per line and 1 for an open site, 0 for a closed one
+    pub fn from_reader<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut hk: Option<HoshenKopelman> = None;
+        for (number, line) in reader.lines().enumerate() {
+            let row = line?
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| match c {
+                    '0' => Ok(false),
+                    '1' => Ok(true),
+                    _ => Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: unexpected {:?}", number + 1, c),
+                    )),
+                })
+                .collect::<io::Result<Vec<bool>>>()?;
+            if row.is_empty() {
+                continue;
+            }
+            let hk = hk.get_or_insert_with(|| HoshenKopelman::new(row.len()));
+            if row.len() != hk.length {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: expected {} sites", number + 1, hk.length),
+                ));
+            }
+            hk.push_row(&row);
+        }
+        hk.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                PercolationError::EmptyGrid.to_string(),
+            )
+        })
+    }
+    fn find(&self, mut label: usize) -> usize {
+        while label != self.parent[label] {
+            label = self.parent[label];
+        }
+        label
+    }
+    fn union(&mut self, label1: usize, label2: usize) {
+        let (root1, root2) = (self.find(label1), self.find(label2));
+        if root1 == root2 {
+            return;
+        }
+        let (root, child) = if self.size[root1] < self.size[root2] {
+            (root2, root1)
+        } else {
+            (root1, root2)
+        };
+        self.parent[child] = root;
+        self.size[root] += self.size[child];
+        self.top[root] |= self.top[child];
+    }
+    pub fn push_row(&mut self, row: &[bool]) {
+        if row.len() != self.length {
+            panic!("expected a row of {} sites, got {}", self.length, row.len());
+        }
+        let previous = self.parent.len();
+        let mut labels = vec![0; self.length];
+        for (col, &open) in row.iter().enumerate() {
+            if !open {
+                continue;
+            }
+            let up = self.labels[col];
+            let left = if col > 0 { labels[col - 1] } else { 0 };
+            let label = match (up, left) {
+                (0, 0) => {
+                    self.parent.push(self.parent.len());
+                    self.size.push(0);
+                    self.top.push(self.rows == 0);
+                    self.parent.len() - 1
+                }
+                (0, label) | (label, 0) => label,
+                (up, left) => {
+                    self.union(up, left);
+                    up
+                }
+            };
+            let root = self.find(label);
+            self.size[root] += 1;
+            labels[col] = label;
+        }
+        // clusters of the previous row that this row does not continue are complete
+        let mut relabel = vec![0; self.parent.len()];
+        for &label in labels.iter().filter(|&&label| label != 0) {
+            relabel[self.find(label)] = 1;
+        }
+        for label in (1..previous).filter(|&label| self.parent[label] == label) {
+            if relabel[label] == 0 {
+                *self.histogram.entry(self.size[label]).or_insert(0) += 1;
+            }
+        }
+        // number the remaining clusters from 1 again
+        let (mut parent, mut size, mut top) = (vec![0], vec![0], vec![false]);
+        for (label, new) in relabel.iter_mut().enumerate().skip(1) {
+            if *new != 0 {
+                *new = parent.len();
+                parent.push(parent.len());
+                size.push(self.size[label]);
+                top.push(self.top[label]);
+            }
+        }
+        for label in labels.iter_mut().filter(|label| **label != 0) {
+            *label = relabel[self.find(*label)];
+        }
+        self.labels = labels;
+        (self.parent, self.size, self.top) = (parent, size, top);
+        self.rows += 1;
+    }
+    // whether the first row is connected to the last row added so far
+    pub fn percolates(&self) -> bool {
+        self.top.iter().skip(1).any(|&top| top) && self.rows > 0
+    }
+    pub fn stats(&self) -> ClusterStats {
+        let finished = self
+            .histogram
+            .iter()
+            .flat_map(|(&size, &count)| iter::repeat_n(size, count));
+        let spanning: Vec<usize> = (1..self.size.len())
+            .filter(|&label| self.top[label])
+            .map(|label| self.size[label])
+            .collect();
+        ClusterStats::from_sizes(
+            finished.chain(self.size.iter().skip(1).copied()),
+            &spanning,
+            self.length * self.rows,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{format, hoshenkopelman::HoshenKopelman};
+    use std::{fs, io};
+
+    #[test]
+    fn hoshen_kopelman_agrees_with_percolation() {
+        let inputs = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../percolationvisualizer/inputs"
+        );
+        for entry in fs::read_dir(inputs).unwrap() {
+            let text = fs::read_to_string(entry.unwrap().path()).unwrap();
+            let perc = format::parse(&text).unwrap().to_percolation();
+            let n = perc.grid_size();
+            let hk = HoshenKopelman::from_rows(
+                n,
+                (1..=n).map(|row| {
+                    (1..=n)
+                        .map(|col| perc.is_open(row, col))
+                        .collect::<Vec<_>>()
+                }),
+            );
+            assert_eq!(hk.percolates(), perc.percolates());
+            assert_eq!(hk.stats(), perc.cluster_stats());
+        }
+    }
+
+    #[test]
+    fn hoshen_kopelman_reads_a_matrix() {
+        let matrix = "1 0 1\n1 1 0\n0 1 0\n";
+        let hk = HoshenKopelman::from_reader(matrix.as_bytes()).unwrap();
+        assert!(hk.percolates());
+        let stats = hk.stats();
+        assert_eq!((stats.clusters, stats.largest), (2, 4));
+        assert!(HoshenKopelman::from_reader("1 0\n1\n".as_bytes()).is_err());
+        for empty in ["", "\n  \n"] {
+            let error = HoshenKopelman::from_reader(empty.as_bytes()).err().unwrap();
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::Percolation;
+use rand::{thread_rng, Rng};
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+use weightedquickunion::WeightedQuickUnionUF;
+
+// a site waiting on the perimeter of the invaded cluster, ordered by strength
+struct Candidate(f64, usize);
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+// Grows the invaded cluster from the top row by always opening the weakest site on its
+// perimeter until the bottom row is reached. With `trapping`, closed regions the cluster has
+// cut off from the bottom row keep their defending fluid and are never invaded.
+pub struct InvasionPercolation {
+    perc: Percolation,
+    strength: Vec<f64>,
+    // sites in the order they are invaded
+    order: Vec<usize>,
+    next: usize,
+    trapped: Vec<bool>,
+}
+
+impl InvasionPercolation {
+    pub fn new(n: usize, trapping: bool) -> Self {
+        let mut rng = thread_rng();
+        let strength = (0..n * n).map(|_| rng.gen::<f64>()).collect();
+        InvasionPercolation::with_strengths(n, strength, trapping)
+    }
+    pub fn with_strengths(n: usize, strength: Vec<f64>, trapping: bool) -> Self {
+        if strength.len() != n * n {
+            panic!("expected {} strengths, got {}", n * n, strength.len());
+        }
+        let mut invasion = InvasionPercolation {
+            perc: Percolation::new(n),
+            strength,
+            order: Vec::new(),
+            next: 0,
+            trapped: vec![false; n * n],
+        };
+        invasion.order = invasion.invasion_order();
+        if trapping {
+            invasion.trap();
+            let trapped = &invasion.trapped;
+            invasion.order.retain(|&index| !trapped[index]);
+        }
+        invasion
+    }
+    // the order of invasion without trapping
+    fn invasion_order(&self) -> Vec<usize> {
+        let n = self.perc.length;
+        let mut order = Vec::new();
+        let mut queued = vec![false; n * n];
+        let mut perimeter = BinaryHeap::new();
+        for (index, queued) in queued.iter_mut().enumerate().take(n) {
+            *queued = true;
+            perimeter.push(Reverse(Candidate(self.strength[index], index)));
+        }
+        while let Some(Reverse(Candidate(_, index))) = perimeter.pop() {
+            order.push(index);
+            if index / n == n - 1 {
+                break;
+            }
+            for (idxnear, _) in self.perc.neighbours(index) {
+                if !queued[idxnear] {
+                    queued[idxnear] = true;
+                    perimeter.push(Reverse(Candidate(self.strength[idxnear], idxnear)));
+                }
+            }
+        }
+        order
+    }
+    // Trapping only takes sites out of the order without trapping, as everything reached
+    // through a trapped site is trapped as well. Walking the order backwards puts the
+    // defending fluid back one site at a time, and a site was trapped when it was invaded
+    // exactly if it is not connected to the bottom row at that point.
+    fn trap(&mut self) {
+        let n = self.perc.length;
+        let bottom = n * n;
+        let mut uf = WeightedQuickUnionUF::new(n * n + 1);
+        let mut defended = vec![true; n * n];
+        for &index in &self.order {
+            defended[index] = false;
+        }
+        let defend = |index: usize, defended: &mut Vec<bool>, uf: &mut WeightedQuickUnionUF| {
+            defended[index] = true;
+            if index / n == n - 1 {
+                uf.union(index, bottom);
+            }
+            for (idxnear, _) in self.perc.neighbours(index) {
+                if defended[idxnear] {
+                    uf.union(index, idxnear);
+                }
+            }
+            uf.find(index) != uf.find(bottom)
+        };
+        let never: Vec<usize> = (0..n * n).filter(|&index| defended[index]).collect();
+        for &index in &never {
+            defend(index, &mut defended, &mut uf);
+        }
+        // sites that are never invaded at all are trapped if they end up cut off
+        for &index in &never {
+            self.trapped[index] = uf.find(index) != uf.find(bottom);
+        }
+        for &index in self.order.iter().rev() {
+            self.trapped[index] = defend(index, &mut defended, &mut uf);
+        }
+    }
+    // invades the weakest perimeter site and returns its (row, col), or None once the
+    // cluster has reached the bottom row
+    pub fn step(&mut self) -> Option<(usize, usize)> {
+        let &index = self.order.get(self.next)?;
+        self.next += 1;
+        let (row, col) = self.perc.coordinates(index);
+        self.perc.open(row, col);
+        Some((row, col))
+    }
+    // invades until the bottom row is reached and returns the number of invaded sites
+    pub fn run(&mut self) -> usize {
+        while self.step().is_some() {}
+        self.perc.number_of_open_sites()
+    }
+    pub fn percolation(&self) -> &Percolation {
+        &self.perc
+    }
+    pub fn strength(&self, row: usize, col: usize) -> f64 {
+        self.strength[self.perc.index(self.perc.adjust(row, col))]
+    }
+    // whether the invasion leaves the site to the defending fluid because it is cut off
+    pub fn is_trapped(&self, row: usize, col: usize) -> bool {
+        self.trapped[self.perc.index(self.perc.adjust(row, col))]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::invasion::InvasionPercolation;
+
+    #[test]
+    fn invasion_percolation_works() {
+        let strength = vec![0.9, 0.1, 0.8, 0.2, 0.6, 0.3, 0.5, 0.7, 0.4];
+        let mut id = InvasionPercolation::with_strengths(3, strength, false);
+        assert_eq!(id.step(), Some((1, 2)));
+        assert_eq!(id.run(), 5);
+        for (row, col) in [(1, 2), (2, 2), (2, 1), (2, 3), (3, 3)] {
+            assert!(id.percolation().is_full(row, col));
+        }
+        assert!(!id.percolation().is_open(3, 1));
+        assert_eq!(id.step(), None);
+
+        // opening (2, 1) and (2, 3) seals off (1, 1) and (1, 3) from the bottom row
+        let strength = vec![0.5, 0.1, 0.5, 0.2, 0.3, 0.2, 0.9, 0.4, 0.9];
+        let mut id = InvasionPercolation::with_strengths(3, strength, true);
+        assert_eq!(id.run(), 5);
+        assert!(id.is_trapped(1, 1) && id.is_trapped(1, 3));
+        assert!(!id.percolation().is_open(1, 1));
+    }
+}
@@ -1,20 +1,181 @@
+use std::{collections::HashSet, error::Error, fmt, mem};
 use weightedquickunion::*;
+pub mod analysis;
+mod bulk;
+pub mod clusters;
+pub mod compact;
+pub mod directed;
+pub mod format;
+pub mod graph;
 pub mod gui;
+pub mod hoshenkopelman;
+pub mod invasion;
+pub mod naive;
+mod path;
+pub mod picture;
+pub mod recording;
+mod sites;
+mod snapshot;
 
 const CLOSE: u8 = 0;
 const OPEN: u8 = 1;
 const TOP: u8 = 2;
 const BOTTOM: u8 = 4;
-const PERCOLATE: u8 = TOP | BOTTOM;
+const LEFT: u8 = 8;
+const RIGHT: u8 = 16;
+const SIDES: u8 = TOP | BOTTOM | LEFT | RIGHT;
+const SOURCE: u8 = 32;
+const SINK: u8 = 64;
 
+// the most memory a site can take: its parent and size, its place in `sites` and `slot`, its
+// displacement, its flags and its mark
+const SITE_BYTES: usize = 6 * mem::size_of::<usize>() + 2;
+
+const WRAP_HORIZONTAL: u8 = 1;
+const WRAP_VERTICAL: u8 = 2;
+
+// (row, col) displacement of a site with respect to its parent in the union-find forest
+type Shift = (isize, isize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Boundary {
+    Free,
+    Periodic,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SiteState {
+    Closed,
+    Open,
+    Full,
+}
+
+impl Side {
+    fn flag(self) -> u8 {
+        match self {
+            Side::Top => TOP,
+            Side::Bottom => BOTTOM,
+            Side::Left => LEFT,
+            Side::Right => RIGHT,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PercolationError {
+    EmptyGrid,
+    TooLarge { n: usize },
+    OutOfBounds { row: usize, col: usize, n: usize },
+}
+
+impl fmt::Display for PercolationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PercolationError::EmptyGrid => write!(f, "Grid size should be positive"),
+            PercolationError::TooLarge { n } => write!(f, "Grid size {} is too large", n),
+            PercolationError::OutOfBounds { row, col, n } => {
+                write!(
+                    f,
+                    "Invalid (row, col): ({},{}) in a grid of size {}",
+                    row, col, n
+                )
+            }
+        }
+    }
+}
+
+impl Error for PercolationError {}
+
+// what a call to `Percolation::open` changed, so that callers can update incrementally
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OpenOutcome {
+    pub opened: bool,
+    pub became_full: bool,
+    pub started_percolating: bool,
+    // (row, col) of every site that became full, including the opened site itself
+    pub newly_full: Vec<(usize, usize)>,
+}
+
+// one entry of the log that lets openings be rolled back
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+enum Change {
+    // a site was opened while the grid-wide state was as recorded here
+    Opened {
+        index: usize,
+        percolates: bool,
+        spanned: u16,
+        wraps: u8,
+    },
+    // the root `child` was attached to a root whose flags were `flags`
+    Merged {
+        child: usize,
+        flags: u8,
+    },
+}
+
+// a call to `open` or `close` that changed the grid, as `undo` and `redo` walk them
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Action {
+    index: usize,
+    opened: bool,
+    // unique among the actions of a grid, so that checkpoints can tell histories apart
+    id: usize,
+}
+
+// A point in the history that `Percolation::restore` can return to, identified by the number of
+// actions before it and the id of the last one, or 0 at the start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    depth: usize,
+    id: usize,
+}
+
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "snapshot::RawPercolation")
+)]
 pub struct Percolation {
     length: usize,
     nopen: usize,
     id: WeightedQuickUnionUF,
     open: Vec<u8>,
     percolates: bool,
+    source: u8,
+    sink: u8,
+    // bit k is set once some cluster has touched exactly the sides in `k << 1`
+    spanned: u16,
+    // SOURCE and SINK bits of individual sites, allocated once any are set
+    marks: Vec<u8>,
+    horizontal: Boundary,
+    vertical: Boundary,
+    shift: Vec<Shift>,
+    wraps: u8,
+    log: Vec<Change>,
+    history: Vec<Action>,
+    redo: Vec<Action>,
+    // number of actions ever recorded, the id of the last one
+    actions: usize,
+    // every site with the closed ones first, allocated once a site is opened at random
+    sites: Vec<usize>,
+    slot: Vec<usize>,
 }
 
+// a placeholder grid of size 0 with no sites, which every accessor reports out of bounds
 impl Default for Percolation {
     fn default() -> Self {
         Percolation {
@@ -23,70 +184,452 @@ impl Default for Percolation {
             id: WeightedQuickUnionUF::default(),
             open: Vec::new(),
             percolates: false,
+            source: TOP,
+            sink: BOTTOM,
+            spanned: 0,
+            marks: Vec::new(),
+            horizontal: Boundary::Free,
+            vertical: Boundary::Free,
+            shift: Vec::new(),
+            wraps: 0,
+            log: Vec::new(),
+            history: Vec::new(),
+            redo: Vec::new(),
+            actions: 0,
+            sites: Vec::new(),
+            slot: Vec::new(),
         }
     }
 }
 
 impl Percolation {
     pub fn new(n: usize) -> Self {
-        let size = n * n;
-        Percolation {
+        Percolation::with_boundaries(n, Boundary::Free, Boundary::Free)
+    }
+    pub fn try_new(n: usize) -> Result<Self, PercolationError> {
+        if n == 0 {
+            return Err(PercolationError::EmptyGrid);
+        }
+        Percolation::allocate(n, Boundary::Free, Boundary::Free)
+    }
+    pub fn with_boundaries(n: usize, horizontal: Boundary, vertical: Boundary) -> Self {
+        match Percolation::allocate(n, horizontal, vertical) {
+            Ok(perc) => perc,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    // fails with `TooLarge` rather than aborting when the grid cannot fit in memory
+    fn allocate(
+        n: usize,
+        horizontal: Boundary,
+        vertical: Boundary,
+    ) -> Result<Self, PercolationError> {
+        let too_large = PercolationError::TooLarge { n };
+        let size = match n.checked_mul(n) {
+            Some(size) if size <= isize::MAX as usize / SITE_BYTES => size,
+            _ => return Err(too_large),
+        };
+        let periodic = horizontal == Boundary::Periodic || vertical == Boundary::Periodic;
+        let id = WeightedQuickUnionUF::try_new(size).map_err(|_| too_large)?;
+        let mut open = Vec::new();
+        open.try_reserve_exact(size).map_err(|_| too_large)?;
+        open.resize(size, 0);
+        // displacements are only needed to detect wrapping clusters
+        let mut shift = Vec::new();
+        if periodic {
+            shift.try_reserve_exact(size).map_err(|_| too_large)?;
+            shift.resize(size, (0, 0));
+        }
+        Ok(Percolation {
             length: n,
             nopen: 0,
-            id: WeightedQuickUnionUF::new(size),
-            open: vec![0; size],
+            id,
+            open,
             percolates: false,
+            source: TOP,
+            sink: BOTTOM,
+            spanned: 0,
+            marks: Vec::new(),
+            horizontal,
+            vertical,
+            shift,
+            wraps: 0,
+            log: Vec::new(),
+            history: Vec::new(),
+            redo: Vec::new(),
+            actions: 0,
+            sites: Vec::new(),
+            slot: Vec::new(),
+        })
+    }
+    fn try_adjust(&self, row: usize, col: usize) -> Result<(usize, usize), PercolationError> {
+        if row > self.length || col > self.length || row < 1 || col < 1 {
+            return Err(PercolationError::OutOfBounds {
+                row,
+                col,
+                n: self.length,
+            });
         }
+        Ok((row - 1, col - 1))
     }
     fn adjust(&self, row: usize, col: usize) -> (usize, usize) {
-        if row > self.length || col > self.length || row < 1 || col < 1 {
-            panic!("Invalid (row, col): ({},{})", row, col);
+        match self.try_adjust(row, col) {
+            Ok(coordinates) => coordinates,
+            Err(e) => panic!("{}", e),
         }
-        (row - 1, col - 1)
     }
     fn index(&self, coordinates: (usize, usize)) -> usize {
         coordinates.0 * self.length + coordinates.1
     }
-    pub fn connect(&mut self, idx: usize, idxnear: usize) -> u8 {
+    // 1-based (row, col) of the site at `index`
+    fn coordinates(&self, index: usize) -> (usize, usize) {
+        (index / self.length + 1, index % self.length + 1)
+    }
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = (usize, Shift)> {
+        let n = self.length;
+        let (row, col) = (index / n, index % n);
+        let horizontal = self.horizontal == Boundary::Periodic;
+        let vertical = self.vertical == Boundary::Periodic;
+        [
+            if col >= 1 {
+                Some((index - 1, (0, -1)))
+            } else if horizontal {
+                Some((index + n - 1, (0, -1)))
+            } else {
+                None
+            },
+            if col + 1 < n {
+                Some((index + 1, (0, 1)))
+            } else if horizontal {
+                Some((index + 1 - n, (0, 1)))
+            } else {
+                None
+            },
+            if row >= 1 {
+                Some((index - n, (-1, 0)))
+            } else if vertical {
+                Some((index + n * (n - 1), (-1, 0)))
+            } else {
+                None
+            },
+            if row + 1 < n {
+                Some((index + n, (1, 0)))
+            } else if vertical {
+                Some((index - n * (n - 1), (1, 0)))
+            } else {
+                None
+            },
+        ]
+        .into_iter()
+        .flatten()
+    }
+    // walks up to the root, summing the displacements along the way
+    fn root_shift(&self, mut node: usize) -> (usize, Shift) {
+        let mut shift = (0, 0);
+        loop {
+            let parent = self.id.parent(node);
+            if parent == node {
+                return (node, shift);
+            }
+            shift.0 += self.shift[node].0;
+            shift.1 += self.shift[node].1;
+            node = parent;
+        }
+    }
+    fn union_shifted(&mut self, idx: usize, idxnear: usize, step: Shift) {
+        let (root, shift) = self.root_shift(idx);
+        let (rootnear, shiftnear) = self.root_shift(idxnear);
+        // position of rootnear relative to root when the grid is unrolled
+        let offset = (
+            shift.0 + step.0 - shiftnear.0,
+            shift.1 + step.1 - shiftnear.1,
+        );
+        if root == rootnear {
+            // reaching the same site at two different unrolled positions means the cluster wraps
+            if offset.0 != 0 {
+                self.wraps |= WRAP_VERTICAL;
+            }
+            if offset.1 != 0 {
+                self.wraps |= WRAP_HORIZONTAL;
+            }
+            return;
+        }
+        if let Some(child) = self.id.union(root, rootnear) {
+            self.merged(child);
+            if child == root {
+                self.shift[root] = (-offset.0, -offset.1);
+            } else {
+                self.shift[rootnear] = offset;
+            }
+        }
+    }
+    fn merged(&mut self, child: usize) {
+        let flags = self.open[self.id.parent(child)];
+        self.log.push(Change::Merged { child, flags });
+    }
+    fn connect(&mut self, idx: usize, idxnear: usize, step: Shift) -> u8 {
         let findnear: usize = self.id.find(idxnear);
         if self.open[findnear] != CLOSE {
-            self.id.union(idx, idxnear);
+            if self.shift.is_empty() {
+                if let Some(child) = self.id.union(idx, idxnear) {
+                    self.merged(child);
+                }
+            } else {
+                self.union_shifted(idx, idxnear, step);
+            }
             return self.open[findnear];
         }
         CLOSE
     }
-    pub fn open(&mut self, row: usize, col: usize) {
-        let (row, col) = self.adjust(row, col);
-        let index = self.index((row, col));
-        if self.open[index] & OPEN == 0 {
-            let mut status = OPEN;
-            if col >= 1 {
-                status |= self.connect(index, index - 1);
-            }
-            if col + 1 < self.length {
-                status |= self.connect(index, index + 1);
+    // flags a site contributes to its cluster on its own
+    fn status(&self, index: usize) -> u8 {
+        let (row, col) = (index / self.length, index % self.length);
+        let mut status = OPEN;
+        if row == 0 {
+            status |= TOP;
+        }
+        if row == self.length - 1 {
+            status |= BOTTOM;
+        }
+        if col == 0 {
+            status |= LEFT;
+        }
+        if col == self.length - 1 {
+            status |= RIGHT;
+        }
+        if !self.marks.is_empty() {
+            status |= self.marks[index];
+        }
+        status
+    }
+    // sites of the cluster containing `start`, in the order a flood from `start` reaches them
+    fn cluster(&self, start: usize) -> Vec<usize> {
+        let root = self.id.find(start);
+        let mut seen = HashSet::from([start]);
+        let mut sites = vec![start];
+        let mut next = 0;
+        while next < sites.len() {
+            for (idxnear, _) in self.neighbours(sites[next]) {
+                if self.open[idxnear] != CLOSE
+                    && !seen.contains(&idxnear)
+                    && self.id.find(idxnear) == root
+                {
+                    seen.insert(idxnear);
+                    sites.push(idxnear);
+                }
             }
-            if row >= 1 {
-                status |= self.connect(index, index - self.length);
+            next += 1;
+        }
+        sites
+    }
+    pub fn open(&mut self, row: usize, col: usize) -> OpenOutcome {
+        let index = self.index(self.adjust(row, col));
+        let outcome = self.open_index(index);
+        if outcome.opened {
+            self.record(index, true);
+        }
+        outcome
+    }
+    // starts a new branch of the history, so the actions that were undone can not be redone
+    fn record(&mut self, index: usize, opened: bool) {
+        self.actions += 1;
+        self.history.push(Action {
+            index,
+            opened,
+            id: self.actions,
+        });
+        self.redo.clear();
+    }
+    fn open_index(&mut self, index: usize) -> OpenOutcome {
+        let mut outcome = OpenOutcome::default();
+        if self.open[index] & OPEN == 0 {
+            self.log.push(Change::Opened {
+                index,
+                percolates: self.percolates,
+                spanned: self.spanned,
+                wraps: self.wraps,
+            });
+            let mut status = self.status(index);
+            // marked open up front so that a site can be its own neighbour on a periodic grid
+            self.open[index] = OPEN;
+            let full = status & self.source != 0
+                || self
+                    .neighbours(index)
+                    .any(|(idxnear, _)| self.open[self.id.find(idxnear)] & self.source != 0);
+            if full {
+                // the clusters that are about to be filled have to be walked before they merge
+                let mut filled = vec![index];
+                let mut roots = vec![index];
+                for (idxnear, _) in self.neighbours(index) {
+                    let root = self.id.find(idxnear);
+                    if self.open[root] != CLOSE
+                        && self.open[root] & self.source == 0
+                        && !roots.contains(&root)
+                    {
+                        roots.push(root);
+                        filled.extend(self.cluster(idxnear));
+                    }
+                }
+                outcome.newly_full = filled
+                    .into_iter()
+                    .map(|idx| self.coordinates(idx))
+                    .collect();
             }
-            if row + 1 < self.length {
-                status |= self.connect(index, index + self.length);
+            for (idxnear, step) in self.neighbours(index) {
+                status |= self.connect(index, idxnear, step);
             }
             let f = self.id.find(index);
-            if row == 0 {
-                self.open[f] |= TOP;
-            }
-            if row == self.length - 1 {
-                self.open[f] |= BOTTOM;
-            }
             self.open[f] |= status;
-            if (self.open[f] & PERCOLATE) == PERCOLATE {
+            self.spanned |= 1 << ((self.open[f] & SIDES) >> 1);
+            if self.open[f] & self.source != 0 && self.open[f] & self.sink != 0 {
+                outcome.started_percolating = !self.percolates;
                 self.percolates = true;
             }
             self.open[index] = self.open[f];
+            self.swap_boundary(index);
             self.nopen += 1;
+            outcome.opened = true;
+            outcome.became_full = full;
+        }
+        outcome
+    }
+    // rolls back the most recent opening and returns the index of the site it opened
+    fn undo_open(&mut self) -> Option<usize> {
+        while let Some(change) = self.log.pop() {
+            match change {
+                Change::Merged { child, flags } => {
+                    let root = self.id.parent(child);
+                    self.id.split(child);
+                    self.open[root] = flags;
+                    if !self.shift.is_empty() {
+                        self.shift[child] = (0, 0);
+                    }
+                }
+                Change::Opened {
+                    index,
+                    percolates,
+                    spanned,
+                    wraps,
+                } => {
+                    self.open[index] = CLOSE;
+                    self.nopen -= 1;
+                    self.swap_boundary(index);
+                    self.percolates = percolates;
+                    self.spanned = spanned;
+                    self.wraps = wraps;
+                    return Some(index);
+                }
+            }
+        }
+        None
+    }
+    // the sites in the order they were opened
+    fn opened(&self) -> Vec<usize> {
+        self.log
+            .iter()
+            .filter_map(|change| match change {
+                Change::Opened { index, .. } => Some(*index),
+                Change::Merged { .. } => None,
+            })
+            .collect()
+    }
+    // replays every opening, for changes that alter the flags already stored on the roots
+    fn rebuild(&mut self) {
+        let opened = self.opened();
+        while self.undo_open().is_some() {}
+        for index in opened {
+            self.open_index(index);
+        }
+    }
+    pub fn close(&mut self, row: usize, col: usize) {
+        let index = self.index(self.adjust(row, col));
+        if self.open[index] & OPEN == 0 {
+            return;
+        }
+        self.close_index(index);
+        self.record(index, false);
+    }
+    fn close_index(&mut self, index: usize) {
+        // roll back to just before the site was opened and redo everything opened after it
+        let mut reopen = Vec::new();
+        while let Some(opened) = self.undo_open() {
+            if opened == index {
+                break;
+            }
+            reopen.push(opened);
+        }
+        for index in reopen.into_iter().rev() {
+            self.open_index(index);
+        }
+    }
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            depth: self.history.len(),
+            id: self.history.last().map_or(0, |action| action.id),
         }
     }
+    // Undoes or redoes actions until the grid is as it was at `checkpoint`. Returns false and
+    // changes nothing if the action that led there has since been undone and replaced.
+    pub fn restore(&mut self, checkpoint: Checkpoint) -> bool {
+        let Checkpoint { depth, id } = checkpoint;
+        let done = self.history.len();
+        let action = if depth == 0 {
+            None
+        } else if depth <= done {
+            self.history.get(depth - 1)
+        } else {
+            self.redo
+                .len()
+                .checked_sub(depth - done)
+                .map(|k| &self.redo[k])
+        };
+        if action.map_or(0, |action| action.id) != id {
+            return false;
+        }
+        while self.history.len() > depth && self.undo().is_some() {}
+        while self.history.len() < depth && self.redo().is_some() {}
+        true
+    }
+    // reverts the last action and returns the site it opened or closed
+    pub fn undo(&mut self) -> Option<(usize, usize)> {
+        let action = self.history.pop()?;
+        if action.opened {
+            self.close_index(action.index);
+        } else {
+            self.open_index(action.index);
+        }
+        self.redo.push(action);
+        Some(self.coordinates(action.index))
+    }
+    // repeats the last undone action, the outcome is empty if it closed a site
+    pub fn redo(&mut self) -> Option<((usize, usize), OpenOutcome)> {
+        let action = self.redo.pop()?;
+        let outcome = if action.opened {
+            self.open_index(action.index)
+        } else {
+            self.close_index(action.index);
+            OpenOutcome::default()
+        };
+        self.history.push(action);
+        Some((self.coordinates(action.index), outcome))
+    }
+    pub fn try_close(&mut self, row: usize, col: usize) -> Result<(), PercolationError> {
+        self.try_adjust(row, col)?;
+        self.close(row, col);
+        Ok(())
+    }
+    pub fn try_open(&mut self, row: usize, col: usize) -> Result<OpenOutcome, PercolationError> {
+        self.try_adjust(row, col)?;
+        Ok(self.open(row, col))
+    }
+    pub fn try_is_open(&self, row: usize, col: usize) -> Result<bool, PercolationError> {
+        self.try_adjust(row, col)?;
+        Ok(self.is_open(row, col))
+    }
+    pub fn try_is_full(&self, row: usize, col: usize) -> Result<bool, PercolationError> {
+        self.try_adjust(row, col)?;
+        Ok(self.is_full(row, col))
+    }
     pub fn is_open(&self, row: usize, col: usize) -> bool {
         if self.open[self.index(self.adjust(row, col))] != 0 {
             return true;
@@ -96,22 +639,108 @@ impl Percolation {
     pub fn is_full(&self, row: usize, col: usize) -> bool {
         let index = self.index(self.adjust(row, col));
         if self.open[index] != 0 {
-            return (self.open[self.id.find(index)] & TOP) == TOP;
+            return self.open[self.id.find(index)] & self.source != 0;
         }
         false
     }
+    // n for an n-by-n grid
+    pub fn grid_size(&self) -> usize {
+        self.length
+    }
     pub fn number_of_open_sites(&self) -> usize {
         self.nopen
     }
     pub fn percolates(&self) -> bool {
         self.percolates
     }
+    // fullness and percolation are measured from `source` to `sink`, top to bottom by default
+    pub fn set_sides(&mut self, source: Side, sink: Side) {
+        self.source = source.flag();
+        self.sink = sink.flag();
+        self.rebuild();
+    }
+    // replaces the source side with the given sites, which need not be on the border
+    pub fn set_sources<I: IntoIterator<Item = (usize, usize)>>(&mut self, sites: I) {
+        self.source = SOURCE;
+        self.mark(SOURCE, sites);
+    }
+    pub fn set_sinks<I: IntoIterator<Item = (usize, usize)>>(&mut self, sites: I) {
+        self.sink = SINK;
+        self.mark(SINK, sites);
+    }
+    fn mark<I: IntoIterator<Item = (usize, usize)>>(&mut self, flag: u8, sites: I) {
+        if self.marks.is_empty() {
+            self.marks = vec![0; self.open.len()];
+        }
+        for mark in self.marks.iter_mut() {
+            *mark &= !flag;
+        }
+        for (row, col) in sites {
+            let index = self.index(self.adjust(row, col));
+            self.marks[index] |= flag;
+        }
+        self.rebuild();
+    }
+    pub fn spans(&self, side1: Side, side2: Side) -> bool {
+        let sides = side1.flag() | side2.flag();
+        (0..16).any(|k: u8| self.spanned & (1 << k) != 0 && (k << 1) & sides == sides)
+    }
+    pub fn spans_vertically(&self) -> bool {
+        self.spans(Side::Top, Side::Bottom)
+    }
+    pub fn spans_horizontally(&self) -> bool {
+        self.spans(Side::Left, Side::Right)
+    }
+    pub fn spans_both(&self) -> bool {
+        self.spans_vertically() && self.spans_horizontally()
+    }
+    pub fn spans_any(&self) -> bool {
+        self.spans_vertically() || self.spans_horizontally()
+    }
+    pub fn wraps_horizontally(&self) -> bool {
+        self.wraps & WRAP_HORIZONTAL != 0
+    }
+    pub fn wraps_vertically(&self) -> bool {
+        self.wraps & WRAP_VERTICAL != 0
+    }
+    pub fn wraps_both(&self) -> bool {
+        self.wraps == WRAP_HORIZONTAL | WRAP_VERTICAL
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::Percolation;
+    use crate::{Boundary, OpenOutcome, Percolation, PercolationError, Side};
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // the parts of the other grids that are checked against `Percolation`
+    pub(crate) trait Grid {
+        fn open(&mut self, row: usize, col: usize);
+        fn is_open(&self, row: usize, col: usize) -> bool;
+        fn number_of_open_sites(&self) -> usize;
+        fn percolates(&self) -> bool;
+    }
+
+    // Opens the same seeded random sites in `grid` and in a `Percolation` until they percolate,
+    // checking that both agree along the way, and returns the `Percolation` to compare fullness.
+    pub(crate) fn open_alongside<G: Grid>(grid: &mut G, n: usize, seed: u64) -> Percolation {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut perc = Percolation::new(n);
+        while !perc.percolates() {
+            let (row, col) = (rng.gen_range(1..=n), rng.gen_range(1..=n));
+            perc.open(row, col);
+            grid.open(row, col);
+            assert_eq!(grid.percolates(), perc.percolates());
+        }
+        for row in 1..=n {
+            for col in 1..=n {
+                assert_eq!(grid.is_open(row, col), perc.is_open(row, col));
+            }
+        }
+        assert_eq!(grid.number_of_open_sites(), perc.number_of_open_sites());
+        perc
+    }
 
     #[test]
     fn percolation_works() {
@@ -124,4 +753,184 @@ mod tests {
         id.open(3, 1);
         assert!(!id.is_full(3, 1));
     }
+
+    #[test]
+    fn periodic_wrapping_works() {
+        let mut id = Percolation::with_boundaries(3, Boundary::Periodic, Boundary::Free);
+        id.open(2, 1);
+        id.open(2, 2);
+        assert!(!id.wraps_horizontally());
+        id.open(2, 3);
+        assert!(id.wraps_horizontally());
+        assert!(!id.wraps_vertically());
+        id.open(1, 2);
+        id.open(3, 2);
+        assert!(id.percolates());
+        assert!(!id.wraps_vertically());
+        let mut id = Percolation::with_boundaries(3, Boundary::Periodic, Boundary::Periodic);
+        id.open(1, 1);
+        id.open(2, 2);
+        id.open(3, 3);
+        id.open(1, 2);
+        id.open(2, 3);
+        assert!(!id.wraps_vertically());
+        id.open(3, 1);
+        assert!(id.wraps_both());
+    }
+
+    #[test]
+    fn side_spanning_works() {
+        let mut id = Percolation::new(3);
+        id.set_sides(Side::Left, Side::Right);
+        id.open(2, 1);
+        id.open(2, 2);
+        assert!(id.is_full(2, 2));
+        assert!(!id.percolates());
+        id.open(2, 3);
+        assert!(id.percolates());
+        assert!(id.spans_horizontally());
+        assert!(!id.spans_vertically());
+        assert!(id.spans(Side::Left, Side::Left));
+        id.open(1, 2);
+        assert!(id.spans(Side::Top, Side::Right));
+        id.open(3, 3);
+        assert!(id.spans_both());
+        id.set_sides(Side::Top, Side::Bottom);
+        assert!(id.percolates());
+    }
+
+    #[test]
+    fn source_and_sink_sites_work() {
+        let mut id = Percolation::new(5);
+        id.open(1, 1);
+        id.open(3, 3);
+        id.set_sources([(3, 3), (1, 1)]);
+        id.set_sinks((1..=5).map(|row| (row, 1)));
+        assert!(id.is_full(3, 3));
+        assert!(id.percolates());
+        id.set_sources([(3, 3)]);
+        assert!(!id.is_full(1, 1));
+        assert!(!id.percolates());
+        id.open(3, 2);
+        assert!(id.is_full(3, 2));
+        assert!(!id.percolates());
+        id.open(3, 1);
+        assert!(id.percolates());
+    }
+
+    #[test]
+    fn open_outcome_works() {
+        let mut id = Percolation::new(3);
+        id.open(2, 1);
+        id.open(3, 1);
+        let outcome = id.open(2, 2);
+        assert!(outcome.opened && !outcome.became_full);
+        assert!(outcome.newly_full.is_empty());
+        let outcome = id.open(1, 2);
+        assert!(outcome.became_full && outcome.started_percolating);
+        assert_eq!(outcome.newly_full.len(), 4);
+        assert_eq!(outcome.newly_full[0], (1, 2));
+        assert!(outcome.newly_full.contains(&(3, 1)));
+        assert_eq!(id.open(1, 2), OpenOutcome::default());
+        let outcome = id.open(1, 3);
+        assert_eq!(outcome.newly_full, vec![(1, 3)]);
+        assert!(!outcome.started_percolating);
+    }
+
+    #[test]
+    fn fallible_api_works() {
+        assert_eq!(
+            Percolation::try_new(0).err(),
+            Some(PercolationError::EmptyGrid)
+        );
+        let mut id = Percolation::try_new(2).unwrap();
+        let out_of_bounds = Err(PercolationError::OutOfBounds {
+            row: 3,
+            col: 1,
+            n: 2,
+        });
+        assert_eq!(id.try_open(3, 1), out_of_bounds);
+        assert_eq!(id.try_is_full(3, 1), out_of_bounds.map(|_| false));
+        assert_eq!(id.try_is_open(0, 1).ok(), None);
+        assert!(id.try_open(2, 1).unwrap().opened);
+        assert_eq!(id.try_is_open(2, 1), Ok(true));
+        assert!(Percolation::default().try_open(1, 1).is_err());
+        // n * n fits in a usize but the sites do not fit in memory
+        let n = 1 << (usize::BITS / 2 - 1);
+        assert_eq!(
+            Percolation::try_new(n).err(),
+            Some(PercolationError::TooLarge { n })
+        );
+    }
+
+    #[test]
+    fn closing_works() {
+        let mut id = Percolation::new(3);
+        id.open(1, 2);
+        id.open(2, 2);
+        id.open(2, 1);
+        id.open(3, 1);
+        assert!(id.percolates());
+        id.close(2, 2);
+        assert!(!id.percolates());
+        assert!(!id.is_open(2, 2));
+        assert!(!id.is_full(2, 1));
+        assert_eq!(id.number_of_open_sites(), 3);
+        id.open(1, 1);
+        assert!(id.percolates());
+        id.close(1, 2);
+        assert!(id.percolates());
+        id.close(1, 1);
+        assert!(!id.is_full(3, 1));
+    }
+
+    #[test]
+    fn undo_and_redo_work() {
+        let mut id = Percolation::new(3);
+        id.open(1, 1);
+        let checkpoint = id.checkpoint();
+        id.open(2, 1);
+        id.open(3, 1);
+        assert!(id.percolates());
+        assert_eq!(id.undo(), Some((3, 1)));
+        assert!(!id.percolates());
+        let (site, outcome) = id.redo().unwrap();
+        assert_eq!(site, (3, 1));
+        assert!(outcome.started_percolating);
+        assert_eq!(id.redo(), None);
+        assert!(id.restore(checkpoint));
+        assert_eq!(id.number_of_open_sites(), 1);
+        assert!(!id.is_open(2, 1));
+        id.open(2, 2);
+        assert_eq!(id.redo(), None);
+        assert_eq!(id.undo(), Some((2, 2)));
+        assert_eq!(id.undo(), Some((1, 1)));
+        assert_eq!(id.undo(), None);
+    }
+
+    #[test]
+    fn checkpoints_tell_histories_apart() {
+        // the checkpointed opening was undone and replaced by another one
+        let mut id = Percolation::new(3);
+        id.open(1, 1);
+        let checkpoint = id.checkpoint();
+        id.undo();
+        id.open(3, 3);
+        assert!(!id.restore(checkpoint));
+        assert!(id.is_open(3, 3) && !id.is_open(1, 1));
+        // closing is part of the history and can be undone and redone
+        let mut id = Percolation::new(3);
+        id.open(1, 1);
+        id.open(2, 1);
+        let checkpoint = id.checkpoint();
+        id.close(1, 1);
+        assert_eq!(id.number_of_open_sites(), 1);
+        assert!(id.restore(checkpoint));
+        assert!(id.is_full(2, 1));
+        let (site, outcome) = id.redo().unwrap();
+        assert_eq!(site, (1, 1));
+        assert!(!outcome.opened && !id.is_open(1, 1));
+        assert_eq!(id.undo(), Some((1, 1)));
+        assert!(id.is_full(2, 1));
+    }
 }
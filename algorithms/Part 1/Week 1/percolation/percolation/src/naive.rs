@@ -0,0 +1,137 @@
+use crate::PercolationError;
+use weightedquickunion::WeightedQuickUnionUF;
+
+// The textbook solution with a virtual top site joined to every open site of the first row and
+// a virtual bottom site joined to every open site of the last row. Once the grid percolates, an
+// open site that only touches the bottom row is reported full through the virtual bottom site.
+// This is the backwash that `Percolation` avoids, kept for comparison.
+pub struct NaiveVirtualSitesPercolation {
+    length: usize,
+    nopen: usize,
+    id: WeightedQuickUnionUF,
+    open: Vec<bool>,
+}
+
+impl NaiveVirtualSitesPercolation {
+    pub fn new(n: usize) -> Self {
+        match NaiveVirtualSitesPercolation::try_new(n) {
+            Ok(perc) => perc,
+            Err(e) => panic!("{}", e),
+        }
+    }
+    pub fn try_new(n: usize) -> Result<Self, PercolationError> {
+        match n.checked_mul(n).and_then(|size| size.checked_add(2)) {
+            Some(2) => Err(PercolationError::EmptyGrid),
+            Some(nodes) => Ok(NaiveVirtualSitesPercolation {
+                length: n,
+                nopen: 0,
+                id: WeightedQuickUnionUF::new(nodes),
+                open: vec![false; nodes - 2],
+            }),
+            None => Err(PercolationError::TooLarge { n }),
+        }
+    }
+    fn adjust(&self, row: usize, col: usize) -> usize {
+        if row > self.length || col > self.length || row < 1 || col < 1 {
+            let n = self.length;
+            panic!("{}", PercolationError::OutOfBounds { row, col, n });
+        }
+        (row - 1) * self.length + col - 1
+    }
+    fn top(&self) -> usize {
+        self.open.len()
+    }
+    fn bottom(&self) -> usize {
+        self.open.len() + 1
+    }
+    pub fn open(&mut self, row: usize, col: usize) {
+        let index = self.adjust(row, col);
+        if self.open[index] {
+            return;
+        }
+        self.open[index] = true;
+        self.nopen += 1;
+        let n = self.length;
+        if row == 1 {
+            self.id.union(index, self.top());
+        }
+        if row == n {
+            self.id.union(index, self.bottom());
+        }
+        let (row, col) = (row - 1, col - 1);
+        let neighbours = [
+            (col >= 1).then(|| index - 1),
+            (col + 1 < n).then(|| index + 1),
+            (row >= 1).then(|| index - n),
+            (row + 1 < n).then(|| index + n),
+        ];
+        for idxnear in neighbours.into_iter().flatten() {
+            if self.open[idxnear] {
+                self.id.union(index, idxnear);
+            }
+        }
+    }
+    pub fn is_open(&self, row: usize, col: usize) -> bool {
+        self.open[self.adjust(row, col)]
+    }
+    // true for backwashed sites as well as the ones really connected to the top row
+    pub fn is_full(&self, row: usize, col: usize) -> bool {
+        let index = self.adjust(row, col);
+        self.open[index] && self.id.find(index) == self.id.find(self.top())
+    }
+    pub fn number_of_open_sites(&self) -> usize {
+        self.nopen
+    }
+    pub fn percolates(&self) -> bool {
+        self.id.find(self.top()) == self.id.find(self.bottom())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        naive::NaiveVirtualSitesPercolation,
+        tests::{open_alongside, Grid},
+        Percolation,
+    };
+
+    impl Grid for NaiveVirtualSitesPercolation {
+        fn open(&mut self, row: usize, col: usize) {
+            self.open(row, col);
+        }
+        fn is_open(&self, row: usize, col: usize) -> bool {
+            self.is_open(row, col)
+        }
+        fn number_of_open_sites(&self) -> usize {
+            self.number_of_open_sites()
+        }
+        fn percolates(&self) -> bool {
+            self.percolates()
+        }
+    }
+
+    #[test]
+    fn naive_percolation_agrees_except_for_backwash() {
+        let n = 20;
+        let mut naive = NaiveVirtualSitesPercolation::new(n);
+        let id = open_alongside(&mut naive, n, 50);
+        for row in 1..=n {
+            for col in 1..=n {
+                // backwash can only add full sites
+                assert!(naive.is_full(row, col) || !id.is_full(row, col));
+            }
+        }
+    }
+
+    #[test]
+    fn naive_percolation_backwashes() {
+        let mut id = Percolation::new(3);
+        let mut naive = NaiveVirtualSitesPercolation::new(3);
+        for (row, col) in [(1, 1), (2, 1), (3, 1), (3, 3)] {
+            id.open(row, col);
+            naive.open(row, col);
+        }
+        assert!(naive.percolates() && naive.is_full(3, 3));
+        assert!(id.percolates() && !id.is_full(3, 3));
+    }
+}
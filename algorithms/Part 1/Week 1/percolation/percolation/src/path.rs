@@ -0,0 +1,89 @@
+use crate::{Percolation, CLOSE};
+use std::collections::VecDeque;
+
+const UNSEEN: usize = usize::MAX;
+
+impl Percolation {
+    // open sites leading from a source site to a sink site, found by always following the site
+    // reached last, which is quick but may wander
+    pub fn percolation_path(&self) -> Option<Vec<(usize, usize)>> {
+        self.search_path(false)
+    }
+    // like `percolation_path`, but found by a breadth-first search, which makes it a shortest one
+    pub fn shortest_percolation_path(&self) -> Option<Vec<(usize, usize)>> {
+        self.search_path(true)
+    }
+    fn search_path(&self, shortest: bool) -> Option<Vec<(usize, usize)>> {
+        if !self.percolates {
+            return None;
+        }
+        let mut from = vec![UNSEEN; self.open.len()];
+        let mut frontier: VecDeque<usize> = (0..self.open.len())
+            .filter(|&index| {
+                self.open[index] != CLOSE
+                    && self.status(index) & self.source != 0
+                    && self.open[self.id.find(index)] & self.sink != 0
+            })
+            .collect();
+        for &index in &frontier {
+            from[index] = index;
+        }
+        let next = |frontier: &mut VecDeque<usize>| {
+            if shortest {
+                frontier.pop_front()
+            } else {
+                frontier.pop_back()
+            }
+        };
+        while let Some(index) = next(&mut frontier) {
+            if self.status(index) & self.sink != 0 {
+                let mut path = vec![index];
+                let mut site = index;
+                while from[site] != site {
+                    site = from[site];
+                    path.push(site);
+                }
+                return Some(
+                    path.into_iter()
+                        .rev()
+                        .map(|idx| self.coordinates(idx))
+                        .collect(),
+                );
+            }
+            for (idxnear, _) in self.neighbours(index) {
+                if self.open[idxnear] != CLOSE && from[idxnear] == UNSEEN {
+                    from[idxnear] = index;
+                    frontier.push_back(idxnear);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Percolation;
+
+    #[test]
+    fn percolation_path_works() {
+        let mut id = Percolation::new(4);
+        assert_eq!(id.percolation_path(), None);
+        for (row, col) in [(1, 1), (1, 2), (1, 3), (1, 4), (2, 4), (3, 4), (3, 3)] {
+            id.open(row, col);
+        }
+        for (row, col) in [(3, 2), (3, 1), (2, 1), (4, 1), (4, 4)] {
+            id.open(row, col);
+        }
+        let shortest = id.shortest_percolation_path().unwrap();
+        assert_eq!(shortest, vec![(1, 1), (2, 1), (3, 1), (4, 1)]);
+        let path = id.percolation_path().unwrap();
+        assert_eq!(path[0].0, 1);
+        assert_eq!(path[path.len() - 1].0, 4);
+        for pair in path.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1);
+            assert!(id.is_full(b.0, b.1));
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::format::PercolationInput;
+use image::{imageops, imageops::FilterType, DynamicImage};
+
+// Scales `image` to n by n cells with `filter` and opens the cells at least as bright as
+// `threshold`, or with `invert` the ones darker than it, in row-major order.
+pub fn image_to_grid(
+    image: &DynamicImage,
+    n: usize,
+    filter: FilterType,
+    threshold: u8,
+    invert: bool,
+) -> PercolationInput {
+    let cells = imageops::resize(&image.to_luma8(), n as u32, n as u32, filter);
+    PercolationInput {
+        n,
+        sites: cells
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| (pixel[0] >= threshold) != invert)
+            .map(|(x, y, _)| (y as usize + 1, x as usize + 1))
+            .collect(),
+        warnings: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{format, picture::image_to_grid};
+    use image::{imageops::FilterType, DynamicImage, GrayImage, Luma};
+
+    #[test]
+    fn image_to_grid_works() {
+        let text = include_str!("../../percolationvisualizer/inputs/heart25.txt");
+        let input = format::parse(text).unwrap();
+        let mut open = input.sites.clone();
+        open.sort();
+        open.dedup();
+        // draw every site as a 4 by 4 block of white or black pixels and read it back
+        let image = GrayImage::from_fn(100, 100, |x, y| {
+            let site = (y as usize / 4 + 1, x as usize / 4 + 1);
+            Luma([if open.binary_search(&site).is_ok() {
+                255
+            } else {
+                0
+            }])
+        });
+        let image = DynamicImage::ImageLuma8(image);
+        for filter in [FilterType::Nearest, FilterType::Triangle] {
+            assert_eq!(image_to_grid(&image, 25, filter, 128, false).sites, open);
+        }
+        let inverted = image_to_grid(&image, 25, FilterType::Nearest, 128, true);
+        assert_eq!(inverted.sites.len(), 25 * 25 - open.len());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{OpenOutcome, Percolation};
+
+// Wraps a `Percolation` and remembers, for every site, the step at which it was opened and
+// the step at which it became full. Step k is the opening of the k-th site, counting from 1.
+pub struct RecordingPercolation {
+    perc: Percolation,
+    step: usize,
+    opened: Vec<Option<usize>>,
+    filled: Vec<Option<usize>>,
+}
+
+impl RecordingPercolation {
+    pub fn new(n: usize) -> Self {
+        Self::from_percolation(Percolation::new(n))
+    }
+    // the sites already open in `perc` are not recorded, later steps count on from them
+    pub fn from_percolation(perc: Percolation) -> Self {
+        let sites = perc.open.len();
+        RecordingPercolation {
+            step: perc.number_of_open_sites(),
+            perc,
+            opened: vec![None; sites],
+            filled: vec![None; sites],
+        }
+    }
+    pub fn open(&mut self, row: usize, col: usize) -> OpenOutcome {
+        let outcome = self.perc.open(row, col);
+        if outcome.opened {
+            self.step += 1;
+            self.opened[self.perc.index((row - 1, col - 1))] = Some(self.step);
+            for &(row, col) in &outcome.newly_full {
+                self.filled[self.perc.index((row - 1, col - 1))] = Some(self.step);
+            }
+        }
+        outcome
+    }
+    pub fn percolation(&self) -> &Percolation {
+        &self.perc
+    }
+    pub fn into_percolation(self) -> Percolation {
+        self.perc
+    }
+    pub fn opened_at(&self, row: usize, col: usize) -> Option<usize> {
+        self.opened[self.perc.index(self.perc.adjust(row, col))]
+    }
+    pub fn filled_at(&self, row: usize, col: usize) -> Option<usize> {
+        self.filled[self.perc.index(self.perc.adjust(row, col))]
+    }
+    // one row of the grid per entry, None where the site was never opened
+    pub fn opened_matrix(&self) -> Vec<Vec<Option<usize>>> {
+        self.opened
+            .chunks(self.perc.length.max(1))
+            .map(|row| row.to_vec())
+            .collect()
+    }
+    // one row of the grid per entry, None where the site never became full
+    pub fn filled_matrix(&self) -> Vec<Vec<Option<usize>>> {
+        self.filled
+            .chunks(self.perc.length.max(1))
+            .map(|row| row.to_vec())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::recording::RecordingPercolation;
+
+    #[test]
+    fn recording_works() {
+        let mut id = RecordingPercolation::new(3);
+        id.open(2, 2);
+        id.open(3, 2);
+        id.open(2, 2);
+        id.open(1, 3);
+        id.open(1, 2);
+        assert_eq!(id.opened_at(2, 2), Some(1));
+        assert_eq!(id.opened_at(1, 1), None);
+        assert_eq!(
+            id.filled_matrix(),
+            vec![
+                vec![None, Some(4), Some(3)],
+                vec![None, Some(4), None],
+                vec![None, Some(4), None],
+            ]
+        );
+        assert!(id.percolation().percolates());
+    }
+}
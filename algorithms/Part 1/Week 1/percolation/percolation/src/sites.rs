@@ -0,0 +1,103 @@
+use crate::{OpenOutcome, Percolation, PercolationError, SiteState, CLOSE};
+
+impl Percolation {
+    fn state_of(&self, index: usize) -> SiteState {
+        if self.open[index] == CLOSE {
+            SiteState::Closed
+        } else if self.open[self.id.find(index)] & self.source != 0 {
+            SiteState::Full
+        } else {
+            SiteState::Open
+        }
+    }
+    pub fn state(&self, row: usize, col: usize) -> SiteState {
+        self.state_of(self.index(self.adjust(row, col)))
+    }
+    // every (row, col) of the grid row by row, with the state of the site there
+    pub fn cells(&self) -> impl Iterator<Item = ((usize, usize), SiteState)> + '_ {
+        (0..self.open.len()).map(|index| (self.coordinates(index), self.state_of(index)))
+    }
+    // open sites, full ones included
+    pub fn open_sites(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.sites_where(|state| state != SiteState::Closed)
+    }
+    pub fn full_sites(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.sites_where(|state| state == SiteState::Full)
+    }
+    pub fn closed_sites(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.sites_where(|state| state == SiteState::Closed)
+    }
+    fn sites_where<F>(&self, keep: F) -> impl Iterator<Item = (usize, usize)> + '_
+    where
+        F: Fn(SiteState) -> bool + 'static,
+    {
+        self.cells()
+            .filter(move |&(_, state)| keep(state))
+            .map(|(coordinates, _)| coordinates)
+    }
+    // the same queries with rows and columns counted from 0, checked before adding 1 so that
+    // `usize::MAX` is reported out of bounds rather than overflowing
+    fn one_based(&self, row: usize, col: usize) -> (usize, usize) {
+        if row >= self.length || col >= self.length {
+            let n = self.length;
+            panic!("{}", PercolationError::OutOfBounds { row, col, n });
+        }
+        (row + 1, col + 1)
+    }
+    pub fn open_zero_based(&mut self, row: usize, col: usize) -> OpenOutcome {
+        let (row, col) = self.one_based(row, col);
+        self.open(row, col)
+    }
+    pub fn close_zero_based(&mut self, row: usize, col: usize) {
+        let (row, col) = self.one_based(row, col);
+        self.close(row, col)
+    }
+    pub fn is_open_zero_based(&self, row: usize, col: usize) -> bool {
+        let (row, col) = self.one_based(row, col);
+        self.is_open(row, col)
+    }
+    pub fn is_full_zero_based(&self, row: usize, col: usize) -> bool {
+        let (row, col) = self.one_based(row, col);
+        self.is_full(row, col)
+    }
+    pub fn state_zero_based(&self, row: usize, col: usize) -> SiteState {
+        let (row, col) = self.one_based(row, col);
+        self.state(row, col)
+    }
+    pub fn cells_zero_based(&self) -> impl Iterator<Item = ((usize, usize), SiteState)> + '_ {
+        self.cells()
+            .map(|((row, col), state)| ((row - 1, col - 1), state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Percolation, SiteState};
+
+    #[test]
+    fn site_iterators_work() {
+        let mut id = Percolation::new(2);
+        id.open(1, 2);
+        id.open_zero_based(1, 0);
+        assert_eq!(id.open_sites().collect::<Vec<_>>(), vec![(1, 2), (2, 1)]);
+        assert_eq!(id.full_sites().collect::<Vec<_>>(), vec![(1, 2)]);
+        assert_eq!(id.closed_sites().count(), 2);
+        assert_eq!(
+            id.cells_zero_based().collect::<Vec<_>>(),
+            vec![
+                ((0, 0), SiteState::Closed),
+                ((0, 1), SiteState::Full),
+                ((1, 0), SiteState::Open),
+                ((1, 1), SiteState::Closed),
+            ]
+        );
+        assert!(id.is_open_zero_based(1, 0) && !id.is_full_zero_based(1, 0));
+        assert_eq!(id.state(2, 1), SiteState::Open);
+    }
+
+    #[test]
+    #[should_panic(expected = "in a grid of size 2")]
+    fn zero_based_accessors_check_bounds_first() {
+        Percolation::new(2).is_open_zero_based(usize::MAX, 0);
+    }
+}
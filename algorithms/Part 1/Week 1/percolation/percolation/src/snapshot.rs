@@ -0,0 +1,555 @@
+use crate::{
+    Action, Boundary, Change, Percolation, BOTTOM, CLOSE, LEFT, OPEN, RIGHT, SINK, SOURCE, TOP,
+};
+use std::io::{self, Read, Write};
+use weightedquickunion::WeightedQuickUnionUF;
+
+const MAGIC: &[u8; 4] = b"PERC";
+const VERSION: u32 = 2;
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn write_usize<W: Write>(writer: &mut W, value: usize) -> io::Result<()> {
+    writer.write_all(&(value as u64).to_le_bytes())
+}
+
+fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> io::Result<[u8; N]> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_usize<R: Read>(reader: &mut R) -> io::Result<usize> {
+    usize::try_from(u64::from_le_bytes(read_bytes(reader)?))
+        .map_err(|_| invalid("value does not fit in usize"))
+}
+
+// length of a per-site vector, which is allocated for every site or not at all
+fn read_len<R: Read>(reader: &mut R, size: usize) -> io::Result<usize> {
+    match read_usize(reader)? {
+        len if len == 0 || len == size => Ok(len),
+        _ => Err(invalid("inconsistent percolation snapshot")),
+    }
+}
+
+// `len` site indices, each below `limit`
+fn read_indices<R: Read>(reader: &mut R, len: usize, limit: usize) -> io::Result<Vec<usize>> {
+    let mut indices = Vec::new();
+    for _ in 0..len {
+        let index = read_usize(reader)?;
+        if index >= limit {
+            return Err(invalid("site index out of range"));
+        }
+        indices.push(index);
+    }
+    Ok(indices)
+}
+
+fn write_actions<W: Write>(writer: &mut W, actions: &[Action]) -> io::Result<()> {
+    write_usize(writer, actions.len())?;
+    for action in actions {
+        write_usize(writer, action.index)?;
+        writer.write_all(&[action.opened as u8])?;
+        write_usize(writer, action.id)?;
+    }
+    Ok(())
+}
+
+fn read_actions<R: Read>(reader: &mut R, limit: usize) -> io::Result<Vec<Action>> {
+    let mut actions = Vec::new();
+    for _ in 0..read_usize(reader)? {
+        let index = read_usize(reader)?;
+        if index >= limit {
+            return Err(invalid("site index out of range"));
+        }
+        let [opened] = read_bytes(reader)?;
+        let id = read_usize(reader)?;
+        actions.push(Action {
+            index,
+            opened: opened != 0,
+            id,
+        });
+    }
+    Ok(actions)
+}
+
+fn boundary(flag: u8) -> io::Result<Boundary> {
+    match flag {
+        0 => Ok(Boundary::Free),
+        1 => Ok(Boundary::Periodic),
+        _ => Err(invalid("unknown boundary")),
+    }
+}
+
+// Checks everything the rest of `Percolation` relies on, so that a damaged or hand-made snapshot
+// is rejected here instead of panicking or looping once it is used.
+fn check(perc: &Percolation) -> io::Result<()> {
+    let size = perc.open.len();
+    if perc.length.checked_mul(perc.length) != Some(size) || perc.id.nodes() != size {
+        return Err(invalid("inconsistent percolation snapshot"));
+    }
+    let per_site = |len: usize| len == 0 || len == size;
+    if !per_site(perc.marks.len()) || !per_site(perc.sites.len()) {
+        return Err(invalid("inconsistent percolation snapshot"));
+    }
+    let indices = perc.log.iter().map(|change| match *change {
+        Change::Opened { index, .. } => index,
+        Change::Merged { child, .. } => child,
+    });
+    let actions = perc.history.iter().chain(&perc.redo);
+    if indices
+        .chain(actions.map(|action| action.index))
+        .chain(perc.sites.iter().copied())
+        .any(|index| index >= size)
+    {
+        return Err(invalid("site index out of range"));
+    }
+    let sides = [TOP, BOTTOM, LEFT, RIGHT, SOURCE, SINK];
+    if !sides.contains(&perc.source) || !sides.contains(&perc.sink) {
+        return Err(invalid("unknown source or sink"));
+    }
+    let periodic = perc.horizontal == Boundary::Periodic || perc.vertical == Boundary::Periodic;
+    if perc.shift.len() != if periodic { size } else { 0 } {
+        return Err(invalid("displacements do not match the boundaries"));
+    }
+    // every displacement to the root adds up steps along a spanning tree of the cluster, so it
+    // is shorter than the number of sites, and roots are not displaced at all
+    for node in 0..perc.shift.len() {
+        let (mut total, mut site) = ((0isize, 0isize), node);
+        while perc.id.parent(site) != site {
+            let (row, col) = perc.shift[site];
+            total = match (total.0.checked_add(row), total.1.checked_add(col)) {
+                (Some(row), Some(col))
+                    if row.unsigned_abs() < size && col.unsigned_abs() < size =>
+                {
+                    (row, col)
+                }
+                _ => return Err(invalid("displacement out of range")),
+            };
+            site = perc.id.parent(site);
+        }
+        if perc.shift[site] != (0, 0) {
+            return Err(invalid("displacement out of range"));
+        }
+    }
+    if perc.marks.iter().any(|&mark| mark & !(SOURCE | SINK) != 0) {
+        return Err(invalid("unknown site mark"));
+    }
+    let open: Vec<bool> = perc.open.iter().map(|&flags| flags != CLOSE).collect();
+    if perc
+        .open
+        .iter()
+        .any(|&flags| flags != CLOSE && flags & OPEN == 0)
+        || open.iter().filter(|&&open| open).count() != perc.nopen
+    {
+        return Err(invalid("open sites do not match their count"));
+    }
+    // undo every change of the log on a copy of the forest, which has to end up with every site
+    // closed and on its own
+    let mut parent: Vec<usize> = (0..size).map(|node| perc.id.parent(node)).collect();
+    let mut children = vec![0; size];
+    for (node, &root) in parent.iter().enumerate() {
+        if root != node {
+            children[root] += 1;
+        }
+    }
+    let mut still_open = open.clone();
+    let mut openings = 0;
+    for change in perc.log.iter().rev() {
+        match *change {
+            Change::Merged { child, .. } => {
+                let root = parent[child];
+                if root == child || parent[root] != root || !still_open[child] || !still_open[root]
+                {
+                    return Err(invalid("undo log does not match the union-find structure"));
+                }
+                parent[child] = child;
+                children[root] -= 1;
+            }
+            Change::Opened { index, .. } => {
+                if !still_open[index] || parent[index] != index || children[index] != 0 {
+                    return Err(invalid("undo log does not match the open sites"));
+                }
+                still_open[index] = false;
+                openings += 1;
+            }
+        }
+    }
+    if openings != perc.nopen || parent.iter().enumerate().any(|(node, &root)| root != node) {
+        return Err(invalid("undo log does not match the open sites"));
+    }
+    // replay the history from an empty grid up to the current one and on through the redo stack
+    let mut state = vec![false; size];
+    let mut last = 0;
+    let redo = perc.redo.iter().rev();
+    for (step, action) in perc.history.iter().chain(redo).enumerate() {
+        if step == perc.history.len() && state != open {
+            return Err(invalid("history does not match the open sites"));
+        }
+        if state[action.index] == action.opened || action.id <= last || action.id > perc.actions {
+            return Err(invalid("inconsistent history"));
+        }
+        state[action.index] = action.opened;
+        last = action.id;
+    }
+    if perc.redo.is_empty() && state != open {
+        return Err(invalid("history does not match the open sites"));
+    }
+    // the permutation used to open random sites lists every site once, the closed ones first,
+    // and `slot` is its inverse
+    if perc.slot.len() != perc.sites.len() {
+        return Err(invalid("inconsistent site permutation"));
+    }
+    let mut seen = vec![false; perc.sites.len()];
+    for (slot, &index) in perc.sites.iter().enumerate() {
+        if seen[index] || (slot < size - perc.nopen) == open[index] || perc.slot[index] != slot {
+            return Err(invalid("inconsistent site permutation"));
+        }
+        seen[index] = true;
+    }
+    Ok(())
+}
+
+// the fields of `Percolation` as deserialized, before they are checked like a snapshot
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+pub(crate) struct RawPercolation {
+    length: usize,
+    nopen: usize,
+    id: WeightedQuickUnionUF,
+    open: Vec<u8>,
+    percolates: bool,
+    source: u8,
+    sink: u8,
+    spanned: u16,
+    marks: Vec<u8>,
+    horizontal: Boundary,
+    vertical: Boundary,
+    shift: Vec<crate::Shift>,
+    wraps: u8,
+    log: Vec<Change>,
+    history: Vec<Action>,
+    redo: Vec<Action>,
+    actions: usize,
+    sites: Vec<usize>,
+    slot: Vec<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawPercolation> for Percolation {
+    type Error = io::Error;
+    fn try_from(raw: RawPercolation) -> io::Result<Self> {
+        let perc = Percolation {
+            length: raw.length,
+            nopen: raw.nopen,
+            id: raw.id,
+            open: raw.open,
+            percolates: raw.percolates,
+            source: raw.source,
+            sink: raw.sink,
+            spanned: raw.spanned,
+            marks: raw.marks,
+            horizontal: raw.horizontal,
+            vertical: raw.vertical,
+            shift: raw.shift,
+            wraps: raw.wraps,
+            log: raw.log,
+            history: raw.history,
+            redo: raw.redo,
+            actions: raw.actions,
+            sites: raw.sites,
+            slot: raw.slot,
+        };
+        check(&perc)?;
+        Ok(perc)
+    }
+}
+
+impl Percolation {
+    // Writes the whole state, union-find structure and undo history included, after a "PERC"
+    // header and a little-endian format version.
+    pub fn write_snapshot<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        write_usize(&mut writer, self.length)?;
+        write_usize(&mut writer, self.nopen)?;
+        writer.write_all(&[
+            self.percolates as u8,
+            self.source,
+            self.sink,
+            self.wraps,
+            (self.horizontal == Boundary::Periodic) as u8,
+            (self.vertical == Boundary::Periodic) as u8,
+        ])?;
+        writer.write_all(&self.spanned.to_le_bytes())?;
+        self.id.write_snapshot(&mut writer)?;
+        writer.write_all(&self.open)?;
+        write_usize(&mut writer, self.marks.len())?;
+        writer.write_all(&self.marks)?;
+        write_usize(&mut writer, self.shift.len())?;
+        for &(row, col) in &self.shift {
+            writer.write_all(&(row as i64).to_le_bytes())?;
+            writer.write_all(&(col as i64).to_le_bytes())?;
+        }
+        write_usize(&mut writer, self.log.len())?;
+        for change in &self.log {
+            match *change {
+                Change::Opened {
+                    index,
+                    percolates,
+                    spanned,
+                    wraps,
+                } => {
+                    writer.write_all(&[0])?;
+                    write_usize(&mut writer, index)?;
+                    writer.write_all(&[percolates as u8, wraps])?;
+                    writer.write_all(&spanned.to_le_bytes())?;
+                }
+                Change::Merged { child, flags } => {
+                    writer.write_all(&[1])?;
+                    write_usize(&mut writer, child)?;
+                    writer.write_all(&[flags])?;
+                }
+            }
+        }
+        write_actions(&mut writer, &self.history)?;
+        write_actions(&mut writer, &self.redo)?;
+        write_usize(&mut writer, self.actions)?;
+        write_usize(&mut writer, self.sites.len())?;
+        for &index in &self.sites {
+            write_usize(&mut writer, index)?;
+        }
+        Ok(())
+    }
+    pub fn read_snapshot<R: Read>(mut reader: R) -> io::Result<Self> {
+        let reader = &mut reader;
+        if &read_bytes::<_, 4>(reader)? != MAGIC {
+            return Err(invalid("not a percolation snapshot"));
+        }
+        let version = u32::from_le_bytes(read_bytes(reader)?);
+        if version != VERSION {
+            return Err(invalid(&format!(
+                "unsupported snapshot version {}",
+                version
+            )));
+        }
+        let length = read_usize(reader)?;
+        let size = length
+            .checked_mul(length)
+            .ok_or_else(|| invalid("grid too large"))?;
+        let nopen = read_usize(reader)?;
+        let [percolates, source, sink, wraps, horizontal, vertical] = read_bytes(reader)?;
+        let spanned = u16::from_le_bytes(read_bytes(reader)?);
+        let id = WeightedQuickUnionUF::read_snapshot(&mut *reader)?;
+        if id.nodes() != size || nopen > size {
+            return Err(invalid("inconsistent percolation snapshot"));
+        }
+        let mut open = vec![0; size];
+        reader.read_exact(&mut open)?;
+        let mut marks = vec![0; read_len(reader, size)?];
+        reader.read_exact(&mut marks)?;
+        let mut shift = Vec::new();
+        for _ in 0..read_len(reader, size)? {
+            let mut read = || {
+                isize::try_from(i64::from_le_bytes(read_bytes(reader)?))
+                    .map_err(|_| invalid("displacement out of range"))
+            };
+            shift.push((read()?, read()?));
+        }
+        let mut log = Vec::new();
+        for _ in 0..read_usize(reader)? {
+            let [tag] = read_bytes(reader)?;
+            let index = read_usize(reader)?;
+            if index >= size {
+                return Err(invalid("site index out of range"));
+            }
+            log.push(match tag {
+                0 => {
+                    let [percolates, wraps] = read_bytes(reader)?;
+                    Change::Opened {
+                        index,
+                        percolates: percolates != 0,
+                        spanned: u16::from_le_bytes(read_bytes(reader)?),
+                        wraps,
+                    }
+                }
+                1 => {
+                    let [flags] = read_bytes(reader)?;
+                    Change::Merged {
+                        child: index,
+                        flags,
+                    }
+                }
+                _ => return Err(invalid("unknown change in undo log")),
+            });
+        }
+        let history = read_actions(reader, size)?;
+        let redo = read_actions(reader, size)?;
+        let actions = read_usize(reader)?;
+        let len = read_len(reader, size)?;
+        let sites = read_indices(reader, len, size)?;
+        let mut slot = vec![0; sites.len()];
+        for (position, &index) in sites.iter().enumerate() {
+            slot[index] = position;
+        }
+        let perc = Percolation {
+            length,
+            nopen,
+            id,
+            open,
+            percolates: percolates != 0,
+            source,
+            sink,
+            spanned,
+            marks,
+            horizontal: boundary(horizontal)?,
+            vertical: boundary(vertical)?,
+            shift,
+            wraps,
+            log,
+            history,
+            redo,
+            actions,
+            sites,
+            slot,
+        };
+        check(&perc)?;
+        Ok(perc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Action, Boundary, Change, Percolation};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::io;
+
+    fn reload(perc: &Percolation) -> io::Result<Percolation> {
+        let mut bytes = Vec::new();
+        perc.write_snapshot(&mut bytes).unwrap();
+        Percolation::read_snapshot(bytes.as_slice())
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut id = Percolation::with_boundaries(5, Boundary::Periodic, Boundary::Free);
+        id.open_rect(1..3, 2..4);
+        id.open_random(&mut rng);
+        id.close(1, 2);
+        id.undo();
+        let mut bytes = Vec::new();
+        id.write_snapshot(&mut bytes).unwrap();
+        let mut copy = Percolation::read_snapshot(bytes.as_slice()).unwrap();
+        let mut again = Vec::new();
+        copy.write_snapshot(&mut again).unwrap();
+        assert_eq!(bytes, again);
+        assert_eq!(copy.redo(), id.redo());
+        assert_eq!(copy.open_random(&mut rng.clone()), id.open_random(&mut rng));
+        assert_eq!(
+            copy.cells().collect::<Vec<_>>(),
+            id.cells().collect::<Vec<_>>()
+        );
+        bytes[0] = b'X';
+        assert!(Percolation::read_snapshot(bytes.as_slice()).is_err());
+        assert!(Percolation::read_snapshot(&again[..again.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn damaged_snapshots_are_rejected() {
+        let damaged = |damage: fn(&mut Percolation)| {
+            let mut id = Percolation::new(3);
+            id.open_rect(1..3, 1..3);
+            id.open_random(&mut StdRng::seed_from_u64(2));
+            let mut copy = reload(&id).unwrap();
+            damage(&mut copy);
+            reload(&copy).is_err()
+        };
+        assert!(damaged(|id| id.nopen += 1));
+        assert!(damaged(|id| id.open[8] = 2));
+        // undoing the merge would split a site that has no parent
+        assert!(damaged(|id| {
+            for change in id.log.iter_mut() {
+                if let Change::Merged { child, .. } = change {
+                    *child = 0;
+                }
+            }
+        }));
+        assert!(damaged(|id| id.history.push(Action {
+            index: 0,
+            opened: true,
+            id: 9,
+        })));
+        assert!(damaged(|id| id.sites.swap(0, 8)));
+    }
+
+    #[test]
+    fn displacements_are_checked() {
+        let damaged = |damage: fn(&mut Percolation, usize)| {
+            let mut id = Percolation::with_boundaries(3, Boundary::Periodic, Boundary::Free);
+            id.open_rect(1..3, 1..4);
+            let mut copy = reload(&id).unwrap();
+            let child = (0..9).find(|&node| copy.id.parent(node) != node).unwrap();
+            damage(&mut copy, child);
+            reload(&copy).is_err()
+        };
+        assert!(!damaged(|_, _| ()));
+        assert!(damaged(|id, child| id.shift[child] = (0, isize::MAX)));
+        assert!(damaged(|id, child| id.shift[child] = (-9, 0)));
+        assert!(damaged(|id, child| {
+            let root = id.id.find(child);
+            id.shift[root] = (1, 0);
+        }));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rejects_damaged_data() {
+        let mut id = Percolation::with_boundaries(3, Boundary::Periodic, Boundary::Free);
+        id.open_rect(1..3, 1..4);
+        id.open_random(&mut StdRng::seed_from_u64(3));
+        let value = serde_json::to_value(&id).unwrap();
+        assert!(serde_json::from_value::<Percolation>(value.clone()).is_ok());
+        let damaged = |damage: fn(&mut serde_json::Value)| {
+            let mut value = value.clone();
+            damage(&mut value);
+            serde_json::from_value::<Percolation>(value).is_err()
+        };
+        // two sites that are each other's parent
+        assert!(damaged(|value| {
+            value["id"]["parent"][0] = 1.into();
+            value["id"]["parent"][1] = 0.into();
+        }));
+        assert!(damaged(|value| value["id"]["parent"][0] = 9.into()));
+        assert!(damaged(|value| value["nopen"] = 0.into()));
+        assert!(damaged(|value| {
+            value["log"].as_array_mut().unwrap().pop();
+        }));
+        assert!(damaged(|value| value["sites"][0] = 9.into()));
+        assert!(damaged(|value| value["slot"][0] = 1.into()));
+        assert!(damaged(|value| {
+            for shift in value["shift"].as_array_mut().unwrap() {
+                shift[1] = (1i64 << 40).into();
+            }
+        }));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trips() {
+        let mut id = Percolation::with_boundaries(4, Boundary::Free, Boundary::Periodic);
+        id.open_rect(1..5, 2..3);
+        id.close(2, 2);
+        id.undo();
+        let json = serde_json::to_string(&id).unwrap();
+        let mut copy: Percolation = serde_json::from_str(&json).unwrap();
+        assert!(copy.percolates() && copy.wraps_vertically());
+        assert_eq!(copy.redo(), id.redo());
+        let (mut bytes, mut again) = (Vec::new(), Vec::new());
+        id.write_snapshot(&mut bytes).unwrap();
+        copy.write_snapshot(&mut again).unwrap();
+        assert_eq!(bytes, again);
+    }
+}
@@ -1,7 +1,10 @@
-use percolation::*;
+use percolation::{
+    directed::{DirectedPercolation, Flow},
+    *,
+};
 use rand::{
     distributions::{Distribution, Uniform},
-    thread_rng,
+    thread_rng, Rng,
 };
 
 const CONFIDENCE_95: f64 = 1.96;
@@ -12,19 +15,35 @@ pub struct PercolationStats {
 
 impl PercolationStats {
     pub fn new(n: usize, trials: usize) -> Self {
+        PercolationStats::sample(n, trials, &mut thread_rng(), |_, rng| {
+            Percolation::new(n).open_until_percolates(rng)
+        })
+    }
+    pub fn directed(n: usize, trials: usize, flow: Flow) -> Self {
+        PercolationStats::directed_with_rng(n, trials, flow, &mut thread_rng())
+    }
+    pub fn directed_with_rng<R: Rng>(n: usize, trials: usize, flow: Flow, rng: &mut R) -> Self {
+        PercolationStats::sample(n, trials, rng, |dist, rng| {
+            let mut p = DirectedPercolation::new(n, flow);
+            while !p.percolates() {
+                p.open(dist.sample(rng), dist.sample(rng));
+            }
+            p.number_of_open_sites()
+        })
+    }
+    fn sample<R, F>(n: usize, trials: usize, rng: &mut R, mut trial: F) -> Self
+    where
+        R: Rng,
+        F: FnMut(&Uniform<usize>, &mut R) -> usize,
+    {
         if n == 0 || trials == 0 {
             panic!("n and trials should both be positive");
         }
         let size = n * n;
         let mut threshold: Vec<f64> = Vec::with_capacity(trials);
         let dist = Uniform::new_inclusive(1, n);
-        let mut rng = thread_rng();
         for _ in 0..trials {
-            let mut p = Percolation::new(n);
-            while !p.percolates() {
-                p.open(dist.sample(&mut rng), dist.sample(&mut rng));
-            }
-            threshold.push(p.number_of_open_sites() as f64 / size as f64);
+            threshold.push(trial(&dist, rng) as f64 / size as f64);
         }
         PercolationStats { threshold }
     }
@@ -58,6 +77,8 @@ impl PercolationStats {
 #[cfg(test)]
 mod tests {
     use crate::PercolationStats;
+    use percolation::directed::Flow;
+    use rand::{rngs::StdRng, SeedableRng};
     #[test]
     #[should_panic]
     fn n_zero_panic() {
@@ -70,4 +91,10 @@ mod tests {
         let ps = PercolationStats::new(100, 0);
         assert_ne!(ps.stddev(), 0.);
     }
+    #[test]
+    fn directed_threshold_is_near_0_6447() {
+        let mut rng = StdRng::seed_from_u64(27);
+        let ps = PercolationStats::directed_with_rng(100, 20, Flow::Diagonal, &mut rng);
+        assert!((ps.mean() - 0.6447).abs() < 0.02, "mean {}", ps.mean());
+    }
 }
@@ -0,0 +1,79 @@
+use image::imageops::FilterType;
+use percolation::{
+    format::{self, Format},
+    picture,
+};
+use std::{fs, process};
+
+const USAGE: &str = "Example usage: cargo run --bin image_to_grid picture.png grid_side_length \
+[--threshold 0-255] [--filter nearest|triangle|catmullrom|gaussian|lanczos3] [--invert] \
+[--format sequence|ascii|matrix] [--output file]";
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut positional = Vec::new();
+    let mut threshold = 128;
+    let mut filter = FilterType::Triangle;
+    let mut invert = false;
+    let mut output_format = Format::Sequence;
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(USAGE);
+        match arg.as_str() {
+            "--threshold" => {
+                threshold = value()?
+                    .parse()
+                    .map_err(|_| "Threshold must be between 0 and 255")?
+            }
+            "--filter" => {
+                filter = match value()?.as_str() {
+                    "nearest" => FilterType::Nearest,
+                    "triangle" => FilterType::Triangle,
+                    "catmullrom" => FilterType::CatmullRom,
+                    "gaussian" => FilterType::Gaussian,
+                    "lanczos3" => FilterType::Lanczos3,
+                    other => return Err(format!("Unknown filter: {}\n{}", other, USAGE)),
+                }
+            }
+            "--invert" => invert = true,
+            "--format" => {
+                output_format = match value()?.as_str() {
+                    "sequence" => Format::Sequence,
+                    "ascii" => Format::Ascii,
+                    "matrix" => Format::Matrix,
+                    other => return Err(format!("Unknown format: {}\n{}", other, USAGE)),
+                }
+            }
+            "--output" | "-o" => output = Some(value()?.clone()),
+            _ => positional.push(arg),
+        }
+    }
+    if positional.len() != 2 {
+        return Err(USAGE.to_string());
+    }
+    let image = image::open(positional[0])
+        .map_err(|e| format!("Failed to read {}: {}", positional[0], e))?;
+    let n = match positional[1].parse() {
+        Ok(n) if n > 0 => n,
+        _ => return Err("Grid side length must be a positive number".to_string()),
+    };
+    let input = picture::image_to_grid(&image, n, filter, threshold, invert);
+    let text = format::write(&input, output_format)
+        .map_err(|e| format!("Failed to write the grid: {}", e))?;
+    match output {
+        Some(path) => {
+            fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path, e))?
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+// errors are printed rather than panicking, which aborts under this workspace's profiles
+fn main() {
+    if let Err(message) = run() {
+        eprintln!("{}", message);
+        process::exit(2);
+    }
+}
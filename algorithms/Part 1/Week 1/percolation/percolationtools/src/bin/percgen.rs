@@ -0,0 +1,180 @@
+use percolation::{
+    format::{self, Format, PercolationInput},
+    Percolation, PercolationError,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use std::{fs, process};
+
+const USAGE: &str = "Example usage: cargo run --bin percgen grid_side_length probability \
+[--sites count] [--order random|row-major] [--seed number] [--percolating|--blocked] \
+[--output file]";
+// random grids tried before falling back to a path for a percolating grid
+const ATTEMPTS: usize = 100;
+
+#[derive(PartialEq)]
+enum Outcome {
+    Any,
+    Percolating,
+    Blocked,
+}
+
+// opens a random top to bottom path of at most `sites` sites, which takes at least n
+fn open_path<R: Rng>(
+    perc: &mut Percolation,
+    n: usize,
+    sites: usize,
+    rng: &mut R,
+) -> Result<(), String> {
+    if sites < n {
+        return Err(format!(
+            "A percolating grid of size {} needs at least {} open sites",
+            n, n
+        ));
+    }
+    let mut spare = sites - n;
+    let mut col = rng.gen_range(1..=n);
+    for row in 1..=n {
+        perc.open(row, col);
+        while spare > 0 && rng.gen_bool(0.5) {
+            col = if rng.gen_bool(0.5) {
+                col.max(2) - 1
+            } else {
+                col.min(n - 1) + 1
+            };
+            if !perc.is_open(row, col) {
+                perc.open(row, col);
+                spare -= 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn generate<R: Rng>(
+    n: usize,
+    sites: usize,
+    outcome: &Outcome,
+    rng: &mut R,
+) -> Result<Percolation, String> {
+    let mut perc = Percolation::try_new(n).map_err(|e| e.to_string())?;
+    match outcome {
+        Outcome::Any => {
+            for _ in 0..sites {
+                perc.open_random(rng);
+            }
+        }
+        // open sites until the grid percolates and top it up to the requested count
+        Outcome::Percolating => {
+            let mut attempts = 1;
+            while perc.open_until_percolates(rng) > sites {
+                perc = Percolation::new(n);
+                if attempts == ATTEMPTS {
+                    open_path(&mut perc, n, sites, rng)?;
+                    break;
+                }
+                attempts += 1;
+            }
+            while perc.number_of_open_sites() < sites {
+                perc.open_random(rng);
+            }
+        }
+        // open sites in random order, skipping any that would make the grid percolate
+        Outcome::Blocked => {
+            let mut order: Vec<(usize, usize)> = (1..=n)
+                .flat_map(|row| (1..=n).map(move |col| (row, col)))
+                .collect();
+            order.shuffle(rng);
+            for (row, col) in order {
+                if perc.number_of_open_sites() == sites {
+                    break;
+                }
+                perc.open(row, col);
+                if perc.percolates() {
+                    perc.undo();
+                }
+            }
+            if perc.number_of_open_sites() < sites {
+                return Err(format!("No blocked grid found with {} open sites", sites));
+            }
+        }
+    }
+    Ok(perc)
+}
+
+fn run() -> Result<(), String> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut positional = Vec::new();
+    let mut count = None;
+    let mut row_major = false;
+    let mut seed = None;
+    let mut outcome = Outcome::Any;
+    let mut output = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(USAGE);
+        match arg.as_str() {
+            "--sites" => {
+                count = Some(
+                    value()?
+                        .parse::<usize>()
+                        .map_err(|_| "Invalid site count")?,
+                )
+            }
+            "--order" => {
+                row_major = match value()?.as_str() {
+                    "random" => false,
+                    "row-major" => true,
+                    other => return Err(format!("Unknown order: {}\n{}", other, USAGE)),
+                }
+            }
+            "--seed" => seed = Some(value()?.parse::<u64>().map_err(|_| "Invalid seed")?),
+            "--percolating" => outcome = Outcome::Percolating,
+            "--blocked" => outcome = Outcome::Blocked,
+            "--output" | "-o" => output = Some(value()?.clone()),
+            _ => positional.push(arg),
+        }
+    }
+    let n = match positional.first().map(|n| n.parse::<usize>()) {
+        Some(Ok(n)) if n > 0 => n,
+        _ => return Err(USAGE.to_string()),
+    };
+    let size = n
+        .checked_mul(n)
+        .ok_or_else(|| PercolationError::TooLarge { n }.to_string())?;
+    let sites = match (count, positional.get(1)) {
+        (Some(count), None) => count,
+        (None, Some(p)) => match p.parse::<f64>() {
+            Ok(p) if (0.0..=1.0).contains(&p) => (p * size as f64).round() as usize,
+            _ => return Err("Probability must be between 0 and 1".to_string()),
+        },
+        _ => return Err(USAGE.to_string()),
+    };
+    if sites > size {
+        return Err(format!("A grid of size {} has only {} sites", n, size));
+    }
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let perc = generate(n, sites, &outcome, &mut rng)?;
+    let mut input = PercolationInput::from_percolation(&perc);
+    if !row_major {
+        input.sites.shuffle(&mut rng);
+    }
+    let text = format::write(&input, Format::Sequence)
+        .map_err(|e| format!("Failed to write the grid: {}", e))?;
+    match output {
+        Some(path) => {
+            fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path, e))?
+        }
+        None => print!("{}", text),
+    }
+    Ok(())
+}
+
+fn main() {
+    if let Err(message) = run() {
+        eprintln!("{}", message);
+        process::exit(2);
+    }
+}
@@ -0,0 +1,162 @@
+use percolation::format;
+use std::{collections::BTreeMap, fs, io::BufReader, path::Path, process};
+
+const USAGE: &str = "Example usage: cargo run --bin verify [inputs_dir] [manifest]";
+
+// Each line of the manifest is a file name, whether it percolates and its number of open sites.
+fn read_manifest(path: &Path) -> Result<BTreeMap<String, (bool, usize)>, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut manifest = BTreeMap::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let problem = match fields[..] {
+            [name, percolates, open] => match (percolates.parse(), open.parse()) {
+                (Ok(percolates), Ok(open)) => {
+                    manifest.insert(name.to_string(), (percolates, open));
+                    continue;
+                }
+                _ => "invalid entry",
+            },
+            _ => "expected 3 fields",
+        };
+        let (path, number) = (path.display(), number + 1);
+        return Err(format!("{}:{}: {}: {:?}", path, number, problem, line));
+    }
+    Ok(manifest)
+}
+
+// what checking a directory of inputs against a manifest found, as "name: problem" lines
+#[derive(Debug, Default)]
+struct Report {
+    checked: usize,
+    warnings: Vec<String>,
+    mismatches: Vec<String>,
+}
+
+// fails when the manifest or the directory cannot be read, rather than reporting mismatches
+fn verify(inputs: &Path, manifest: &Path) -> Result<Report, String> {
+    let mut expected = read_manifest(manifest)?;
+    let mut names: Vec<String> = fs::read_dir(inputs)
+        .map_err(|e| format!("Failed to read {}: {}", inputs.display(), e))?
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .filter(|name| name.ends_with(".txt"))
+        .collect();
+    names.sort();
+    let mut report = Report {
+        checked: names.len(),
+        ..Report::default()
+    };
+    let mut mismatch = |name: &str, problem: String| {
+        report.mismatches.push(format!("{}: {}", name, problem));
+    };
+    let mut warnings = Vec::new();
+    for name in &names {
+        let file = match fs::File::open(inputs.join(name)) {
+            Ok(file) => file,
+            Err(e) => {
+                mismatch(name, e.to_string());
+                continue;
+            }
+        };
+        // read the inputs as the visualizers do, so a stray or repeated site is only a warning
+        let input = match format::read_input(BufReader::new(file)) {
+            Ok(input) => input,
+            Err(e) => {
+                mismatch(name, e.to_string());
+                continue;
+            }
+        };
+        for warning in &input.warnings {
+            warnings.push(format!("{}: warning: {}", name, warning));
+        }
+        let perc = input.to_percolation();
+        let (percolates, open) = (perc.percolates(), perc.number_of_open_sites());
+        // by convention files ending in -no must not percolate
+        if name.trim_end_matches(".txt").ends_with("-no") && percolates {
+            mismatch(name, "percolates but is named -no".to_string());
+        }
+        match expected.remove(name) {
+            Some(entry) if entry != (percolates, open) => mismatch(
+                name,
+                format!(
+                    "expected percolates {} with {} open sites, got percolates {} with {}",
+                    entry.0, entry.1, percolates, open
+                ),
+            ),
+            Some(_) => (),
+            None => mismatch(name, "not in the manifest".to_string()),
+        }
+    }
+    for name in expected.keys() {
+        mismatch(name, "listed in the manifest but missing".to_string());
+    }
+    report.warnings = warnings;
+    Ok(report)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    // usage and manifest problems exit with 2, so that they are told apart from mismatches
+    if args.len() > 2 {
+        eprintln!("{}", USAGE);
+        process::exit(2);
+    }
+    let workspace = concat!(env!("CARGO_MANIFEST_DIR"), "/..");
+    let inputs = match args.first() {
+        Some(dir) => Path::new(dir).to_path_buf(),
+        None => Path::new(workspace).join("percolationvisualizer/inputs"),
+    };
+    let manifest = match args.get(1) {
+        Some(path) => Path::new(path).to_path_buf(),
+        None => Path::new(workspace).join("percolationvisualizer/expected.txt"),
+    };
+    let report = verify(&inputs, &manifest).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        process::exit(2);
+    });
+    for line in report.warnings.iter().chain(&report.mismatches) {
+        println!("{}", line);
+    }
+    println!(
+        "{} inputs checked, {} warnings, {} mismatches",
+        report.checked,
+        report.warnings.len(),
+        report.mismatches.len()
+    );
+    if !report.mismatches.is_empty() {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{read_manifest, verify};
+    use std::{fs, path::Path};
+
+    #[test]
+    fn inputs_match_the_manifest() {
+        let visualizer = Path::new(env!("CARGO_MANIFEST_DIR")).join("../percolationvisualizer");
+        let report = verify(&visualizer.join("inputs"), &visualizer.join("expected.txt")).unwrap();
+        assert!(report.checked > 0);
+        assert_eq!(report.mismatches, Vec::<String>::new());
+        // input8-dups.txt opens some sites twice on purpose
+        assert!(report
+            .warnings
+            .iter()
+            .any(|warning| warning.starts_with("input8-dups.txt: warning:")));
+    }
+
+    #[test]
+    fn malformed_manifests_are_reported() {
+        let path = std::env::temp_dir().join("percolationtools-malformed-manifest.txt");
+        fs::write(&path, "# name percolates open\nheart25.txt false\n").unwrap();
+        let error = read_manifest(&path).unwrap_err();
+        fs::remove_file(&path).unwrap();
+        assert!(error.ends_with(":2: expected 3 fields: \"heart25.txt false\""));
+    }
+}
@@ -1,13 +1,18 @@
 #![windows_subsystem = "windows"]
 
-use minifb::{Icon, InputCallback, Key, Menu, Window, WindowOptions, MENU_KEY_CTRL};
-use percolation::{gui::*, Percolation};
+use minifb::{Icon, InputCallback, Key, KeyRepeat, Menu, Window, WindowOptions, MENU_KEY_CTRL};
+use percolation::{
+    gui::*, naive::NaiveVirtualSitesPercolation, recording::RecordingPercolation, Percolation,
+};
 use std::{thread, time::Duration};
 
 const DELAY: u64 = 1000; // increase this value to slow the animation speed and vice versa
 
 const OPEN_INPUT: usize = 1;
 const SAVE_SCREEN: usize = 2;
+const SHOW_PATH: usize = 3;
+const COMPARE_NAIVE: usize = 4;
+const SHOW_HEATMAP: usize = 5;
 
 struct KeyCharCallback;
 
@@ -18,7 +23,7 @@ impl InputCallback for KeyCharCallback {
 }
 
 fn main() {
-    let mut input_file;
+    let mut input_file = Vec::new();
     let mut input = [].iter();
     let mut n_deref = 0;
     let mut buffer = vec![0; AREA];
@@ -47,6 +52,18 @@ fn main() {
         .shortcut(Key::S, MENU_KEY_CTRL)
         .build();
 
+    menu.add_item("Toggle shortest percolation path", SHOW_PATH)
+        .shortcut(Key::P, MENU_KEY_CTRL)
+        .build();
+
+    menu.add_item("Compare with naive virtual sites", COMPARE_NAIVE)
+        .shortcut(Key::B, MENU_KEY_CTRL)
+        .build();
+
+    menu.add_item("Toggle fill-time heat map", SHOW_HEATMAP)
+        .shortcut(Key::H, MENU_KEY_CTRL)
+        .build();
+
     if let Some(menus) = window.get_posix_menus() {
         println!("Menus {:?}", menus);
     }
@@ -55,53 +72,180 @@ fn main() {
     #[cfg(target_os = "windows")]
     window.set_icon(<Icon as std::str::FromStr>::from_str("percolation.ico").unwrap());
 
+    // a placeholder with no sites until an input is opened, see `init`
     let mut perc = Percolation::default();
     let mut block_half_length = 0;
     let font = set_font();
 
     let mut init = false;
+    let mut paused = false;
+    // second window running the naive solution on the same sites, with backwash in red
+    let mut naive: Option<(Window, NaiveVirtualSitesPercolation, Vec<u32>)> = None;
+    let mut naive_stale = false;
+    let mut naive_step = None;
+    // the highlighted path is drawn over each frame, and only looked for again once an opening
+    // fills sites or a step back empties them
+    let mut show_path = false;
+    let mut path: Option<Vec<(usize, usize)>> = None;
+    let mut path_stale = false;
+    // the whole input replayed with the step at which each site filled, shown instead of the grid
+    let mut heatmap: Option<(RecordingPercolation, Vec<u32>)> = None;
 
     while window.is_open() && !window.is_key_down(Key::Escape) {
-        if let Some(&idy) = input.next() {
-            let &idx = input.next().unwrap();
-            perc.open(idy, idx);
-            for idx in 1..=n_deref {
-                for idy in 1..=n_deref {
-                    if perc.is_open(idy, idx) {
-                        if perc.is_full(idy, idx) {
-                            fill_rect(idx, idy, n_deref, block_half_length, 6801139, &mut buffer);
-                        //rgb(103,198,243)
-                        } else {
-                            fill_rect(idx, idy, n_deref, block_half_length, 16777215, &mut buffer);
-                        }
+        // Space pauses, Left steps back and Right steps forward while paused
+        if window.is_key_pressed(Key::Space, KeyRepeat::No) {
+            paused = !paused;
+        }
+        if window.is_key_pressed(Key::Left, KeyRepeat::Yes) {
+            paused = true;
+            if perc.undo().is_some() {
+                buffer = vec![0; AREA];
+                fill_grid(&perc, n_deref, block_half_length, &mut buffer);
+                naive_stale = true;
+                path_stale = true;
+            }
+        } else if !paused || window.is_key_pressed(Key::Right, KeyRepeat::Yes) {
+            // sites undone by stepping back are replayed before the rest of the input
+            if let Some(((idy, idx), outcome)) = perc.redo() {
+                fill_outcome(idx, idy, &outcome, n_deref, block_half_length, &mut buffer);
+                naive_step = Some((idy, idx));
+                path_stale |= outcome.became_full;
+            } else if let Some(&(idy, idx)) = input.next() {
+                match perc.try_open(idy, idx) {
+                    Ok(outcome) => {
+                        fill_outcome(idx, idy, &outcome, n_deref, block_half_length, &mut buffer);
+                        naive_step = Some((idy, idx));
+                        path_stale |= outcome.became_full;
+                    }
+                    Err(e) => {
+                        message_box("Invalid input file", e.to_string().as_str());
+                        input = [].iter();
                     }
                 }
+            } else {
+                thread::sleep(Duration::from_millis(100));
             }
+        }
+        if show_path && path_stale {
+            path = perc.shortest_percolation_path();
+            path_stale = false;
+        }
+        let mut offscreenbuffer;
+        if let Some((recording, heat)) = &heatmap {
+            offscreenbuffer = heat.clone();
+            offscreenbuffer.extend(draw_status_bar(recording.percolation(), init, None, &font));
         } else {
-            thread::sleep(Duration::from_millis(100));
+            offscreenbuffer = buffer.clone();
+            let shown = path.as_ref().filter(|_| show_path);
+            for &(idy, idx) in shown.into_iter().flatten() {
+                fill_rect(
+                    idx,
+                    idy,
+                    n_deref,
+                    block_half_length,
+                    16753920,
+                    &mut offscreenbuffer,
+                );
+                //rgb(255,165,0)
+            }
+            let path_length = shown.map(|path| path.len() - 1);
+            offscreenbuffer.extend(draw_status_bar(&perc, init, path_length, &font));
         }
-        let mut offscreenbuffer = buffer.clone();
-        offscreenbuffer.extend(draw_status_bar(&perc, init, &font));
         if let Some(menu_id) = window.is_menu_pressed() {
             match menu_id {
                 SAVE_SCREEN => {
                     save_screen(&mut offscreenbuffer);
                 }
+                SHOW_PATH => {
+                    show_path = !show_path;
+                    path_stale = true;
+                }
+                SHOW_HEATMAP if heatmap.is_some() || !init => heatmap = None,
+                SHOW_HEATMAP => {
+                    let mut recording = RecordingPercolation::new(n_deref);
+                    for &(idy, idx) in &input_file {
+                        recording.open(idy, idx);
+                    }
+                    let mut heat = vec![0; AREA];
+                    fill_heatmap(&recording, n_deref, block_half_length, &mut heat);
+                    heatmap = Some((recording, heat));
+                    paused = true;
+                }
+                COMPARE_NAIVE => {
+                    naive = match naive {
+                        Some(_) => None,
+                        None => Window::new(
+                            "Naive virtual sites - backwash in red",
+                            LENGTH,
+                            LENGTH,
+                            WindowOptions::default(),
+                        )
+                        .map(|w| (w, NaiveVirtualSitesPercolation::new(1), vec![0; AREA]))
+                        .map_err(|e| message_box("Unable to Open Window", &e.to_string()))
+                        .ok(),
+                    };
+                    naive_stale = true;
+                }
                 OPEN_INPUT => {
-                    input_file = open();
-                    input = input_file.iter();
-                    if let Some(n) = input.next() {
-                        n_deref = *n;
-                        buffer = vec![0; AREA];
-                        perc = Percolation::new(n_deref);
-                        block_half_length = half_length(n_deref);
-                        init = true;
+                    if let Some(file) = open() {
+                        let n = file.n;
+                        input_file = file.sites;
+                        input = input_file.iter();
+                        match Percolation::try_new(n) {
+                            Ok(p) => {
+                                n_deref = n;
+                                buffer = vec![0; AREA];
+                                perc = p;
+                                block_half_length = half_length(n_deref);
+                                init = true;
+                                paused = false;
+                                naive_stale = true;
+                                path_stale = true;
+                                heatmap = None;
+                            }
+                            Err(e) => {
+                                message_box("Invalid input file", e.to_string().as_str());
+                                input = [].iter();
+                            }
+                        }
                     }
                 }
                 _ => (),
             }
         }
 
+        if let Some((naive_window, naive_perc, naive_buffer)) = &mut naive {
+            // the naive solution cannot close sites, so it is only rebuilt after a step back
+            if naive_stale && init {
+                *naive_perc = NaiveVirtualSitesPercolation::new(n_deref);
+                for (idy, idx) in perc.open_sites() {
+                    naive_perc.open(idy, idx);
+                }
+                *naive_buffer = vec![0; AREA];
+                fill_backwash(naive_perc, &perc, n_deref, block_half_length, naive_buffer);
+            } else if let Some((idy, idx)) = naive_step {
+                let percolated = naive_perc.percolates();
+                naive_perc.open(idy, idx);
+                fill_backwash_step(
+                    naive_perc,
+                    &perc,
+                    (idy, idx),
+                    percolated,
+                    n_deref,
+                    block_half_length,
+                    naive_buffer,
+                );
+            }
+            naive_window
+                .update_with_buffer(naive_buffer, LENGTH, LENGTH)
+                .unwrap();
+            if !naive_window.is_open() {
+                naive = None;
+            }
+        }
+        naive_stale = false;
+        naive_step = None;
+
         // We unwrap here as we want this code to exit if it fails
         window
             .update_with_buffer(&offscreenbuffer, LENGTH, LENGTH + STATUS_HEIGHT)
@@ -1,9 +1,42 @@
+use std::collections::TryReserveError;
+use std::io::{self, Read, Write};
+
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RawWeightedQuickUnionUF")
+)]
 pub struct WeightedQuickUnionUF {
     parent: Vec<usize>,
     size: Vec<usize>,
     count: usize,
 }
 
+// the fields as deserialized, before they are checked like a snapshot
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct RawWeightedQuickUnionUF {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+    count: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<RawWeightedQuickUnionUF> for WeightedQuickUnionUF {
+    type Error = &'static str;
+    fn try_from(raw: RawWeightedQuickUnionUF) -> Result<Self, Self::Error> {
+        let uf = WeightedQuickUnionUF {
+            parent: raw.parent,
+            size: raw.size,
+            count: raw.count,
+        };
+        if !uf.is_consistent() {
+            return Err("inconsistent union-find structure");
+        }
+        Ok(uf)
+    }
+}
+
 impl Default for WeightedQuickUnionUF {
     fn default() -> Self {
         WeightedQuickUnionUF {
@@ -22,9 +55,22 @@ impl WeightedQuickUnionUF {
             count,
         }
     }
+    // like `new`, but reports running out of memory instead of aborting
+    pub fn try_new(count: usize) -> Result<Self, TryReserveError> {
+        let mut uf = WeightedQuickUnionUF::default();
+        uf.parent.try_reserve_exact(count)?;
+        uf.size.try_reserve_exact(count)?;
+        uf.parent.extend(0..count);
+        uf.size.resize(count, 1);
+        uf.count = count;
+        Ok(uf)
+    }
     pub fn count(&self) -> usize {
         self.count
     }
+    pub fn nodes(&self) -> usize {
+        self.parent.len()
+    }
     pub fn find(&self, mut node: usize) -> usize {
         if node >= self.parent.len() {
             panic!("index {} is not between 0 and {}", node, self.count - 1);
@@ -34,24 +80,114 @@ impl WeightedQuickUnionUF {
         }
         node
     }
+    pub fn parent(&self, node: usize) -> usize {
+        self.parent[node]
+    }
+    // number of nodes in the component of `node`
+    pub fn size(&self, node: usize) -> usize {
+        self.size[self.find(node)]
+    }
     #[deprecated]
     pub fn connected(&self, node1: usize, node2: usize) -> bool {
         self.find(node1) == self.find(node2)
     }
-    pub fn union(&mut self, node1: usize, node2: usize) {
+    pub fn union(&mut self, node1: usize, node2: usize) -> Option<usize> {
         let root1 = self.find(node1);
         let root2 = self.find(node2);
         if root1 == root2 {
-            return;
+            return None;
         }
+        self.count -= 1;
         if self.size[root1] < self.size[root2] {
             self.parent[root1] = root2;
             self.size[root2] += self.size[root1];
+            Some(root1)
         } else {
             self.parent[root2] = root1;
             self.size[root1] += self.size[root2];
+            Some(root2)
         }
-        self.count -= 1;
+    }
+    // undoes the union that attached the root `child`, so later unions must be split first
+    pub fn split(&mut self, child: usize) {
+        let root = self.parent[child];
+        if root == child {
+            panic!("node {} is not attached to another root", child);
+        }
+        self.size[root] -= self.size[child];
+        self.parent[child] = child;
+        self.count += 1;
+    }
+    // little-endian count and number of nodes, then the parent and size of every node
+    pub fn write_snapshot<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&(self.count as u64).to_le_bytes())?;
+        writer.write_all(&(self.parent.len() as u64).to_le_bytes())?;
+        for (&parent, &size) in self.parent.iter().zip(&self.size) {
+            writer.write_all(&(parent as u64).to_le_bytes())?;
+            writer.write_all(&(size as u64).to_le_bytes())?;
+        }
+        Ok(())
+    }
+    pub fn read_snapshot<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut read = || -> io::Result<usize> {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            usize::try_from(u64::from_le_bytes(bytes))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        };
+        let count = read()?;
+        let nodes = read()?;
+        let mut uf = WeightedQuickUnionUF::default();
+        for _ in 0..nodes {
+            uf.parent.push(read()?);
+            uf.size.push(read()?);
+        }
+        uf.count = count;
+        if !uf.is_consistent() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "inconsistent union-find snapshot",
+            ));
+        }
+        Ok(uf)
+    }
+    // Whether the parents form trees, every size is the number of nodes in the subtree below it
+    // as `split` relies on, and `count` is the number of trees.
+    fn is_consistent(&self) -> bool {
+        let nodes = self.parent.len();
+        if self.parent.iter().any(|&parent| parent >= nodes) {
+            return false;
+        }
+        // depth of every node, found by walking up to a root or to a node already measured
+        let mut depth = vec![usize::MAX; nodes];
+        let mut path = Vec::new();
+        for start in 0..nodes {
+            let mut node = start;
+            while depth[node] == usize::MAX && self.parent[node] != node {
+                if path.len() == nodes {
+                    return false;
+                }
+                path.push(node);
+                node = self.parent[node];
+            }
+            if depth[node] == usize::MAX {
+                depth[node] = 0;
+            }
+            while let Some(node) = path.pop() {
+                depth[node] = depth[self.parent[node]] + 1;
+            }
+        }
+        // add every subtree to its parent, deepest nodes first
+        let mut order: Vec<usize> = (0..nodes).collect();
+        order.sort_unstable_by_key(|&node| std::cmp::Reverse(depth[node]));
+        let mut size = vec![1; nodes];
+        for node in order {
+            if self.parent[node] != node {
+                size[self.parent[node]] += size[node];
+            }
+        }
+        let roots = (0..nodes).filter(|&node| self.parent[node] == node).count();
+        size == self.size && roots == self.count
     }
 }
 
@@ -67,6 +203,58 @@ mod tests {
             uf.union(0, 1);
         }
         assert_eq!(uf.find(0), uf.find(1));
+        assert_eq!(uf.size(1), 2);
         assert_eq!(uf.count(), 1)
     }
+
+    #[test]
+    fn split_undoes_union() {
+        let mut uf = WeightedQuickUnionUF::new(3);
+        let first = uf.union(0, 1).unwrap();
+        let second = uf.union(2, 0).unwrap();
+        assert_eq!(uf.union(1, 2), None);
+        uf.split(second);
+        uf.split(first);
+        assert_eq!(uf.count(), 3);
+        assert!((0..3).all(|node| uf.find(node) == node));
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        let mut uf = WeightedQuickUnionUF::new(4);
+        uf.union(0, 1);
+        uf.union(3, 1);
+        let mut bytes = Vec::new();
+        uf.write_snapshot(&mut bytes).unwrap();
+        let copy = WeightedQuickUnionUF::read_snapshot(bytes.as_slice()).unwrap();
+        assert_eq!(copy.count(), 2);
+        assert!((0..4).all(|node| copy.find(node) == uf.find(node)));
+        assert!(WeightedQuickUnionUF::read_snapshot(&bytes[..20]).is_err());
+        // two nodes that are each other's parent
+        let cycle: Vec<u8> = [0u64, 2, 1, 1, 0, 1]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        assert!(WeightedQuickUnionUF::read_snapshot(cycle.as_slice()).is_err());
+        bytes[16 + 8] += 1;
+        assert!(WeightedQuickUnionUF::read_snapshot(bytes.as_slice()).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializing_checks_the_structure() {
+        let mut uf = WeightedQuickUnionUF::new(3);
+        uf.union(0, 1);
+        let json = serde_json::to_string(&uf).unwrap();
+        let copy: WeightedQuickUnionUF = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy.find(1), uf.find(1));
+        for damaged in [
+            r#"{"parent":[1,0,2],"size":[2,2,1],"count":1}"#,
+            r#"{"parent":[0,0,3],"size":[2,1,1],"count":2}"#,
+            r#"{"parent":[0,0,2],"size":[2,1,1],"count":1}"#,
+            r#"{"parent":[0,0,2],"size":[2,1],"count":2}"#,
+        ] {
+            assert!(serde_json::from_str::<WeightedQuickUnionUF>(damaged).is_err());
+        }
+    }
 }